@@ -0,0 +1,323 @@
+use std::{
+    error, fmt,
+    ops::{Add, Mul, Sub},
+};
+
+use crate::{
+    float::Float,
+    helpers::degrees_to_radians,
+    vec3::{Point3, Vec3},
+};
+
+#[derive(Copy, Clone)]
+pub enum Interpolation {
+    Linear,
+    /// Uniform Catmull-Rom spline passing through every keyframe.
+    CatmullRom,
+    /// Cubic Bezier segments using the handles of each keyframe. Keyframes without handles get
+    /// automatic ones, which makes them behave like `CatmullRom`.
+    Bezier,
+}
+
+#[derive(Copy, Clone)]
+pub struct Keyframe<T> {
+    /// Time in frames.
+    pub time: Float,
+    pub value: T,
+    /// Incoming and outgoing Bezier control points, as absolute values.
+    pub handles: Option<(T, T)>,
+}
+
+/// Values that can be blended between keyframes.
+pub trait Animatable:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Float, Output = Self>
+{
+}
+
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Float, Output = T>> Animatable for T {}
+
+#[derive(Debug, PartialEq)]
+pub enum TrackError {
+    NoKeyframes,
+}
+
+impl fmt::Display for TrackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackError::NoKeyframes => write!(f, "a track needs at least one keyframe"),
+        }
+    }
+}
+
+impl error::Error for TrackError {}
+
+/// Time-varying value defined by keyframes sorted by time. Before the first and after the last
+/// keyframe the value is held constant.
+pub struct Track<T: Animatable> {
+    keyframes: Vec<Keyframe<T>>,
+    pub interpolation: Interpolation,
+}
+
+impl<T: Animatable> Track<T> {
+    /// Track through `(time, value)` pairs, which may come in any order.
+    pub fn new(interpolation: Interpolation, keys: &[(Float, T)]) -> Result<Track<T>, TrackError> {
+        let keyframes = keys
+            .iter()
+            .map(|(time, value)| Keyframe {
+                time: *time,
+                value: *value,
+                handles: None,
+            })
+            .collect();
+        Track::from_keyframes(interpolation, keyframes)
+    }
+
+    /// Track through `keyframes`, which may come in any order and carry their own Bezier
+    /// handles.
+    pub fn from_keyframes(
+        interpolation: Interpolation,
+        keyframes: Vec<Keyframe<T>>,
+    ) -> Result<Track<T>, TrackError> {
+        if keyframes.is_empty() {
+            return Err(TrackError::NoKeyframes);
+        }
+        let mut keyframes = keyframes;
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(Track {
+            keyframes,
+            interpolation,
+        })
+    }
+
+    /// Track holding a single value at all times.
+    pub fn constant(value: T) -> Track<T> {
+        Track {
+            keyframes: vec![Keyframe {
+                time: 0.0,
+                value,
+                handles: None,
+            }],
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    pub fn sample(&self, time: Float) -> T {
+        let keys = &self.keyframes;
+        let last = keys.len() - 1;
+        if time <= keys[0].time {
+            return keys[0].value;
+        }
+        if time >= keys[last].time {
+            return keys[last].value;
+        }
+        let i = keys.partition_point(|k| k.time <= time) - 1;
+        let s = (time - keys[i].time) / (keys[i + 1].time - keys[i].time);
+
+        let p0 = keys[i.saturating_sub(1)].value;
+        let p1 = keys[i].value;
+        let p2 = keys[i + 1].value;
+        let p3 = keys[(i + 2).min(last)].value;
+        match self.interpolation {
+            Interpolation::Linear => p1 + (p2 - p1) * s,
+            Interpolation::CatmullRom => {
+                let s2 = s * s;
+                let s3 = s2 * s;
+                (p1 * 2.0
+                    + (p2 - p0) * s
+                    + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * s2
+                    + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * s3)
+                    * 0.5
+            }
+            Interpolation::Bezier => {
+                let (c1, c2) = self.control_handles(i);
+                let r = 1.0 - s;
+                p1 * (r * r * r)
+                    + c1 * (3.0 * r * r * s)
+                    + c2 * (3.0 * r * s * s)
+                    + p2 * (s * s * s)
+            }
+        }
+    }
+
+    /// Values whose convex hull holds every value the track takes: the keyframes, and the Bezier
+    /// control points between them when the track is curved.
+    pub fn control_points(&self) -> Vec<T> {
+        let mut points: Vec<T> = self.keyframes.iter().map(|k| k.value).collect();
+        if !matches!(self.interpolation, Interpolation::Linear) {
+            for i in 0..self.keyframes.len() - 1 {
+                let (c1, c2) = self.control_handles(i);
+                points.extend([c1, c2]);
+            }
+        }
+        points
+    }
+
+    /// Inner control points of the cubic Bezier segment from keyframe `i` to the next. A uniform
+    /// Catmull-Rom segment is the Bezier segment with the automatic handles.
+    fn control_handles(&self, i: usize) -> (T, T) {
+        let keys = &self.keyframes;
+        let last = keys.len() - 1;
+        let p0 = keys[i.saturating_sub(1)].value;
+        let p1 = keys[i].value;
+        let p2 = keys[i + 1].value;
+        let p3 = keys[(i + 2).min(last)].value;
+        let handles = |key: &Keyframe<T>| match self.interpolation {
+            Interpolation::Bezier => key.handles,
+            _ => None,
+        };
+        let c1 = match handles(&keys[i]) {
+            Some((_, out)) => out,
+            None => p1 + (p2 - p0) * (1.0 / 6.0),
+        };
+        let c2 = match handles(&keys[i + 1]) {
+            Some((incoming, _)) => incoming,
+            None => p2 - (p3 - p1) * (1.0 / 6.0),
+        };
+        (c1, c2)
+    }
+}
+
+/// Keyframed parameters of a perspective camera.
+pub struct CameraAnimation {
+    pub look_from: Track<Point3>,
+    pub look_at: Track<Point3>,
+    /// Vertical field of view in degrees.
+    pub vfov: Track<Float>,
+}
+
+impl CameraAnimation {
+    /// Returns `(look_from, look_at, vfov)` at `time`.
+    pub fn sample(&self, time: Float) -> (Point3, Point3, Float) {
+        (
+            self.look_from.sample(time),
+            self.look_at.sample(time),
+            self.vfov.sample(time),
+        )
+    }
+}
+
+/// Keyframed rotation, uniform scale and translation of an object, applied in that order.
+pub struct Transform {
+    pub translation: Track<Vec3>,
+    /// Euler angles in degrees, turning the object about the x, then the y, then the z axis
+    /// through its origin.
+    pub rotation: Track<Vec3>,
+    pub scale: Track<Float>,
+}
+
+impl Transform {
+    pub fn at(&self, time: Float) -> Pose {
+        let degrees = self.rotation.sample(time);
+        let (sin_x, cos_x) = degrees_to_radians(degrees.x).sin_cos();
+        let (sin_y, cos_y) = degrees_to_radians(degrees.y).sin_cos();
+        let (sin_z, cos_z) = degrees_to_radians(degrees.z).sin_cos();
+        Pose {
+            translation: self.translation.sample(time),
+            scale: self.scale.sample(time),
+            sin: Vec3 {
+                x: sin_x,
+                y: sin_y,
+                z: sin_z,
+            },
+            cos: Vec3 {
+                x: cos_x,
+                y: cos_y,
+                z: cos_z,
+            },
+        }
+    }
+}
+
+/// `Transform` at one instant, mapping between the object's own space and the scene's.
+pub struct Pose {
+    pub translation: Vec3,
+    pub scale: Float,
+    sin: Vec3,
+    cos: Vec3,
+}
+
+impl Pose {
+    pub fn point_to_world(&self, p: Point3) -> Point3 {
+        self.rotate(self.scale * p) + self.translation
+    }
+
+    pub fn point_to_local(&self, p: Point3) -> Point3 {
+        self.unrotate(p - self.translation) / self.scale
+    }
+
+    /// Maps a difference of points, like a ray direction or a tangent, to the scene.
+    pub fn vector_to_world(&self, v: Vec3) -> Vec3 {
+        self.rotate(self.scale * v)
+    }
+
+    pub fn vector_to_local(&self, v: Vec3) -> Vec3 {
+        self.unrotate(v) / self.scale
+    }
+
+    /// Maps a unit normal to the scene, where it stays a unit normal.
+    pub fn normal_to_world(&self, n: Vec3) -> Vec3 {
+        self.rotate(n)
+    }
+
+    pub fn rotate(&self, v: Vec3) -> Vec3 {
+        let (s, c) = (self.sin, self.cos);
+        let v = Vec3 {
+            x: v.x,
+            y: c.x * v.y - s.x * v.z,
+            z: s.x * v.y + c.x * v.z,
+        };
+        let v = Vec3 {
+            x: c.y * v.x + s.y * v.z,
+            y: v.y,
+            z: c.y * v.z - s.y * v.x,
+        };
+        Vec3 {
+            x: c.z * v.x - s.z * v.y,
+            y: s.z * v.x + c.z * v.y,
+            z: v.z,
+        }
+    }
+
+    pub fn unrotate(&self, v: Vec3) -> Vec3 {
+        let (s, c) = (self.sin, self.cos);
+        let v = Vec3 {
+            x: c.z * v.x + s.z * v.y,
+            y: c.z * v.y - s.z * v.x,
+            z: v.z,
+        };
+        let v = Vec3 {
+            x: c.y * v.x - s.y * v.z,
+            y: v.y,
+            z: s.y * v.x + c.y * v.z,
+        };
+        Vec3 {
+            x: v.x,
+            y: c.x * v.y + s.x * v.z,
+            z: c.x * v.z - s.x * v.y,
+        }
+    }
+
+    /// Bound on the rotated components of any vector whose components are bounded by `v`.
+    pub fn rotate_bound(&self, v: Vec3) -> Vec3 {
+        let (s, c) = (self.sin.abs(), self.cos.abs());
+        let v = Vec3 {
+            x: v.x,
+            y: c.x * v.y + s.x * v.z,
+            z: s.x * v.y + c.x * v.z,
+        };
+        let v = Vec3 {
+            x: c.y * v.x + s.y * v.z,
+            y: v.y,
+            z: c.y * v.z + s.y * v.x,
+        };
+        Vec3 {
+            x: c.z * v.x + s.z * v.y,
+            y: s.z * v.x + c.z * v.y,
+            z: v.z,
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use crate::{
+    float::Float,
+    hittable::HitRecord,
+    image::Image,
+    ppm::{generate_ppm_linear, save_ppm},
+    ray::Ray,
+    vec3::{Color, Point3, Vec3},
+};
+
+/// First-hit data of a single pixel, used for compositing and debugging passes.
+#[derive(Copy, Clone)]
+pub struct AovSample {
+    pub normal: Vec3,
+    pub depth: Float,
+    pub position: Point3,
+    pub albedo: Color,
+    pub object_id: usize,
+    material_key: usize,
+}
+
+impl AovSample {
+    pub fn from_hit(ray: &Ray, hit_record: &HitRecord) -> AovSample {
+        let material = hit_record.material.as_ref().unwrap();
+        AovSample {
+            normal: hit_record.normal,
+            depth: hit_record.t * ray.direction.len(),
+            position: hit_record.p,
+            albedo: material.albedo(hit_record),
+            object_id: hit_record.object_id,
+            material_key: Arc::as_ptr(material) as usize,
+        }
+    }
+}
+
+/// Per-pixel first-hit buffers stored in the same row-major, top-down order as the beauty pass.
+/// Pixels whose first ray escaped the scene hold `None`.
+pub struct AovBuffers {
+    pub width: i32,
+    pub height: i32,
+    samples: Vec<Option<AovSample>>,
+}
+
+impl AovBuffers {
+    pub fn new(width: i32, height: i32) -> AovBuffers {
+        AovBuffers {
+            width,
+            height,
+            samples: vec![None; (width * height) as usize],
+        }
+    }
+
+    pub fn record(&mut self, x: i32, y: i32, sample: Option<AovSample>) {
+        self.samples[(y * self.width + x) as usize] = sample;
+    }
+
+    /// Assigns material ids in order of first appearance so they are stable between runs.
+    pub fn material_ids(&self) -> Vec<Option<usize>> {
+        let mut keys: Vec<usize> = vec![];
+        self.samples
+            .iter()
+            .map(|sample| {
+                sample.map(|s| match keys.iter().position(|k| *k == s.material_key) {
+                    Some(id) => id,
+                    None => {
+                        keys.push(s.material_key);
+                        keys.len() - 1
+                    }
+                })
+            })
+            .collect()
+    }
+
+    pub fn normal_image(&self) -> Image {
+        self.map(|s| 0.5 * (s.normal + ONE))
+    }
+
+    pub fn depth_image(&self) -> Image {
+        let max_depth = self
+            .samples
+            .iter()
+            .flatten()
+            .map(|s| s.depth)
+            .fold(0.0, Float::max);
+        self.map(|s| (1.0 - s.depth / max_depth) * ONE)
+    }
+
+    pub fn position_image(&self) -> Image {
+        let hits = self.samples.iter().flatten();
+        let mut min = Vec3 {
+            x: Float::MAX,
+            y: Float::MAX,
+            z: Float::MAX,
+        };
+        let mut max = -min;
+        for s in hits {
+            min = Vec3 {
+                x: min.x.min(s.position.x),
+                y: min.y.min(s.position.y),
+                z: min.z.min(s.position.z),
+            };
+            max = Vec3 {
+                x: max.x.max(s.position.x),
+                y: max.y.max(s.position.y),
+                z: max.z.max(s.position.z),
+            };
+        }
+        let extent = max - min;
+        self.map(|s| {
+            let offset = s.position - min;
+            Color {
+                x: offset.x / extent.x.max(Float::EPSILON),
+                y: offset.y / extent.y.max(Float::EPSILON),
+                z: offset.z / extent.z.max(Float::EPSILON),
+            }
+        })
+    }
+
+    pub fn albedo_image(&self) -> Image {
+        self.map(|s| s.albedo)
+    }
+
+    pub fn object_id_image(&self) -> Image {
+        self.map(|s| id_to_color(s.object_id))
+    }
+
+    pub fn material_id_image(&self) -> Image {
+        let pixels = self
+            .material_ids()
+            .iter()
+            .map(|id| id.map_or(BLACK, id_to_color))
+            .collect();
+        Image::from_pixels(self.width, self.height, pixels)
+    }
+
+    /// Writes every pass as `<prefix>_<pass>.ppm`.
+    pub fn save(&self, prefix: &str) -> Result<(), std::io::Error> {
+        let passes = [
+            ("normal", self.normal_image()),
+            ("depth", self.depth_image()),
+            ("position", self.position_image()),
+            ("albedo", self.albedo_image()),
+            ("object_id", self.object_id_image()),
+            ("material_id", self.material_id_image()),
+        ];
+        for (name, image) in passes.iter() {
+            let result = generate_ppm_linear(image);
+            save_ppm(result.as_str(), format!("{}_{}.ppm", prefix, name).as_str())?;
+        }
+        Ok(())
+    }
+
+    fn map(&self, f: impl Fn(&AovSample) -> Color) -> Image {
+        let pixels = self
+            .samples
+            .iter()
+            .map(|sample| sample.as_ref().map_or(BLACK, &f))
+            .collect();
+        Image::from_pixels(self.width, self.height, pixels)
+    }
+}
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+const ONE: Vec3 = Vec3 {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+};
+
+/// Hashes an id into a bright, well separated color.
+fn id_to_color(id: usize) -> Color {
+    let mut h = (id as u32).wrapping_add(1).wrapping_mul(0x9E37_79B9);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 13;
+    Color {
+        x: 0.25 + 0.75 * ((h & 0xFF) as Float / 255.0),
+        y: 0.25 + 0.75 * (((h >> 8) & 0xFF) as Float / 255.0),
+        z: 0.25 + 0.75 * (((h >> 16) & 0xFF) as Float / 255.0),
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    camera::CameraModel,
+    float::Float,
+    hittable::{HitRecord, RayPacket, World},
+    ray::Ray,
+};
+
+/// Number of times the rays are traced by each method, keeping the fastest run.
+const RUNS: usize = 3;
+
+/// Traces one primary ray through the center of every pixel with each way of intersecting the
+/// scene, printing how fast they are and how many of their hits differ from those found by
+/// testing every object in turn. Objects with stochastic alpha masks are hit at random, so
+/// only scenes without them are expected to match exactly.
+pub fn run(world: &World, camera: &dyn CameraModel, image_width: i32, image_height: i32) {
+    let rays: Vec<Ray> = (0..image_height)
+        .flat_map(|j| (0..image_width).map(move |i| (i, j)))
+        .map(|(i, j)| {
+            camera.get_ray(
+                (i as Float + 0.5) / image_width as Float,
+                (j as Float + 0.5) / image_height as Float,
+            )
+        })
+        .collect();
+    let (linear_time, reference) = time(|| {
+        rays.iter()
+            .map(|ray| world.hit_linear(ray, 0.0, Float::MAX))
+            .collect()
+    });
+    report("linear", rays.len(), linear_time, &reference, &reference);
+    let (bvh_time, hits) = time(|| {
+        rays.iter()
+            .map(|ray| world.hit(ray, 0.0, Float::MAX))
+            .collect()
+    });
+    report("bvh", rays.len(), bvh_time, &reference, &hits);
+    for width in [4, 8] {
+        // Rays of neighbouring pixels are chosen in runs along the rows, so a packet stays
+        // coherent.
+        let packets: Vec<RayPacket> = rays
+            .chunks(width)
+            .map(|chunk| RayPacket::new(chunk.to_vec()))
+            .collect();
+        let (packet_time, hits) = time(|| {
+            packets
+                .iter()
+                .flat_map(|packet| world.hit_packet(packet, 0.0, Float::MAX))
+                .collect()
+        });
+        report(
+            format!("bvh, {}-wide packets", width).as_str(),
+            rays.len(),
+            packet_time,
+            &reference,
+            &hits,
+        );
+    }
+}
+
+fn time(trace: impl Fn() -> Vec<Option<HitRecord>>) -> (Duration, Vec<Option<HitRecord>>) {
+    let mut fastest = Duration::MAX;
+    let mut hits = vec![];
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        hits = trace();
+        fastest = fastest.min(start.elapsed());
+    }
+    (fastest, hits)
+}
+
+/// Hits are the same when they are found on the same object at the same distance, everything
+/// else recorded about them being computed from those alone.
+fn report(
+    method: &str,
+    rays: usize,
+    elapsed: Duration,
+    reference: &[Option<HitRecord>],
+    hits: &[Option<HitRecord>],
+) {
+    let mismatches = reference
+        .iter()
+        .zip(hits)
+        .filter(|(a, b)| match (a, b) {
+            (Some(a), Some(b)) => a.object_id != b.object_id || a.t.to_bits() != b.t.to_bits(),
+            (None, None) => false,
+            _ => true,
+        })
+        .count();
+    println!(
+        "{:<22} {:>8.2} ms {:>8.2} Mrays/s {:>6} mismatches",
+        method,
+        elapsed.as_secs_f64() * 1e3,
+        rays as f64 / elapsed.as_secs_f64() / 1e6,
+        mismatches
+    );
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use crate::{
+    float::{consts::PI, Float},
+    helpers::{random_float, random_float_in_range, random_in_unit_disk},
+    ppm::read_ppm,
+    vec3::Vec3,
+};
+
+/// Shape of the lens opening, which is also the shape of out-of-focus highlights.
+#[derive(Clone)]
+pub enum Aperture {
+    Circular,
+    /// Regular polygon formed by `blades` diaphragm blades, rotated by `rotation` degrees.
+    Polygon {
+        blades: u32,
+        rotation: Float,
+    },
+    Mask(Arc<ApertureMask>),
+}
+
+impl Aperture {
+    /// Returns a point on the aperture scaled to the unit disk, with `z` set to zero.
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            Aperture::Circular => random_in_unit_disk(),
+            Aperture::Polygon { blades, rotation } => sample_polygon(*blades, *rotation),
+            Aperture::Mask(mask) => mask.sample(),
+        }
+    }
+}
+
+fn sample_polygon(blades: u32, rotation: Float) -> Vec3 {
+    let sector_angle = 2.0 * PI / blades as Float;
+    let sector = (random_float() * blades as Float)
+        .floor()
+        .min((blades - 1) as Float);
+    let start = rotation.to_radians() + sector * sector_angle;
+    let end = start + sector_angle;
+
+    let mut a = random_float();
+    let mut b = random_float();
+    if a + b > 1.0 {
+        a = 1.0 - a;
+        b = 1.0 - b;
+    }
+    Vec3 {
+        x: a * start.cos() + b * end.cos(),
+        y: a * start.sin() + b * end.sin(),
+        z: 0.0,
+    }
+}
+
+/// Grayscale image stretched over the `[-1, 1]` square; brighter pixels let more light through.
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    transmission: Vec<Float>,
+}
+
+impl ApertureMask {
+    pub fn from_ppm(path: &str) -> Result<ApertureMask, std::io::Error> {
+        let image = read_ppm(path)?;
+        let transmission: Vec<Float> = image
+            .pixels()
+            .iter()
+            .map(|c| 0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z)
+            .collect();
+        if transmission.iter().all(|t| *t <= 0.0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "aperture mask is completely opaque",
+            ));
+        }
+        Ok(ApertureMask {
+            width: image.width as usize,
+            height: image.height as usize,
+            transmission,
+        })
+    }
+
+    fn sample(&self) -> Vec3 {
+        loop {
+            let p = Vec3 {
+                x: random_float_in_range(-1.0, 1.0),
+                y: random_float_in_range(-1.0, 1.0),
+                z: 0.0,
+            };
+            let column = (((p.x + 1.0) / 2.0 * self.width as Float) as usize).min(self.width - 1);
+            let row = (((1.0 - p.y) / 2.0 * self.height as Float) as usize).min(self.height - 1);
+            if random_float() < self.transmission[row * self.width + column] {
+                return p;
+            }
+        }
+    }
+}
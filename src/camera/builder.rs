@@ -0,0 +1,288 @@
+use std::{error, fmt};
+
+use crate::{
+    float::Float,
+    helpers::degrees_to_radians,
+    hittable::World,
+    ray::Ray,
+    vec3::{cross_product, dot_product, Point3, Vec3},
+};
+
+use super::{look_at_basis, Camera, Lens};
+
+#[derive(Debug, PartialEq)]
+pub enum CameraError {
+    LookAtEqualsLookFrom,
+    ZeroUpVector,
+    UpParallelToViewDirection,
+    InvalidFieldOfView(Float),
+    InvalidFocalLength(Float),
+    InvalidSensorSize(Float, Float),
+    InvalidAperture(Float),
+    InvalidFocusDistance(Float),
+    InvalidAspectRatio(Float),
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraError::LookAtEqualsLookFrom => write!(f, "look_at must differ from look_from"),
+            CameraError::ZeroUpVector => write!(f, "v_up must not be a zero vector"),
+            CameraError::UpParallelToViewDirection => {
+                write!(f, "v_up must not be parallel to the viewing direction")
+            }
+            CameraError::InvalidFieldOfView(fov) => {
+                write!(
+                    f,
+                    "field of view must be between 0 and 180 degrees, got {}",
+                    fov
+                )
+            }
+            CameraError::InvalidFocalLength(length) => {
+                write!(f, "focal length must be positive, got {}", length)
+            }
+            CameraError::InvalidSensorSize(width, height) => {
+                write!(f, "sensor size must be positive, got {}x{}", width, height)
+            }
+            CameraError::InvalidAperture(aperture) => {
+                write!(f, "aperture must not be negative, got {}", aperture)
+            }
+            CameraError::InvalidFocusDistance(distance) => {
+                write!(f, "focus distance must be positive, got {}", distance)
+            }
+            CameraError::InvalidAspectRatio(ratio) => {
+                write!(f, "aspect ratio must be positive, got {}", ratio)
+            }
+        }
+    }
+}
+
+impl error::Error for CameraError {}
+
+enum FieldOfView {
+    Vertical(Float),
+    FocalLength(Float),
+}
+
+enum ApertureSize {
+    Diameter(Float),
+    FNumber(Float),
+}
+
+enum Focus<'a> {
+    LookAt,
+    Distance(Float),
+    Auto(&'a World, Float),
+}
+
+/// Builds a thin-lens `Camera` from either a field of view or physical lens parameters.
+/// Lengths given in millimeters are converted to scene units with `scene_units_per_mm`.
+pub struct CameraBuilder<'a> {
+    look_from: Point3,
+    look_at: Point3,
+    v_up: Vec3,
+    aspect_ratio: Option<Float>,
+    field_of_view: FieldOfView,
+    sensor_size: (Float, Float),
+    aperture: ApertureSize,
+    focus: Focus<'a>,
+    scene_units_per_mm: Float,
+    lens: Lens,
+}
+
+impl Default for CameraBuilder<'_> {
+    fn default() -> Self {
+        CameraBuilder {
+            look_from: Point3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            look_at: Point3 {
+                x: 0.0,
+                y: 0.0,
+                z: -1.0,
+            },
+            v_up: Vec3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+            aspect_ratio: None,
+            field_of_view: FieldOfView::Vertical(90.0),
+            sensor_size: (36.0, 24.0),
+            aperture: ApertureSize::Diameter(0.0),
+            focus: Focus::LookAt,
+            scene_units_per_mm: 0.001,
+            lens: Lens::default(),
+        }
+    }
+}
+
+impl<'a> CameraBuilder<'a> {
+    pub fn look_from(mut self, look_from: Point3) -> Self {
+        self.look_from = look_from;
+        self
+    }
+
+    pub fn look_at(mut self, look_at: Point3) -> Self {
+        self.look_at = look_at;
+        self
+    }
+
+    pub fn v_up(mut self, v_up: Vec3) -> Self {
+        self.v_up = v_up;
+        self
+    }
+
+    /// Defaults to the aspect ratio of the sensor.
+    pub fn aspect_ratio(mut self, aspect_ratio: Float) -> Self {
+        self.aspect_ratio = Some(aspect_ratio);
+        self
+    }
+
+    /// Vertical field of view in degrees.
+    pub fn vertical_fov(mut self, degrees: Float) -> Self {
+        self.field_of_view = FieldOfView::Vertical(degrees);
+        self
+    }
+
+    /// Focal length in millimeters; the field of view follows from the sensor height.
+    pub fn focal_length(mut self, millimeters: Float) -> Self {
+        self.field_of_view = FieldOfView::FocalLength(millimeters);
+        self
+    }
+
+    /// Sensor width and height in millimeters, full frame by default.
+    pub fn sensor_size(mut self, width: Float, height: Float) -> Self {
+        self.sensor_size = (width, height);
+        self
+    }
+
+    /// Aperture diameter in scene units.
+    pub fn aperture(mut self, diameter: Float) -> Self {
+        self.aperture = ApertureSize::Diameter(diameter);
+        self
+    }
+
+    /// Aperture as the ratio of focal length to diameter.
+    pub fn f_number(mut self, f_number: Float) -> Self {
+        self.aperture = ApertureSize::FNumber(f_number);
+        self
+    }
+
+    /// Defaults to the distance between `look_from` and `look_at`.
+    pub fn focus_distance(mut self, distance: Float) -> Self {
+        self.focus = Focus::Distance(distance);
+        self
+    }
+
+    /// Focuses on whatever the center of the image sees in `world` at `time`, falling back to
+    /// `look_at` when the center ray escapes.
+    pub fn auto_focus(mut self, world: &'a World, time: Float) -> Self {
+        self.focus = Focus::Auto(world, time);
+        self
+    }
+
+    pub fn scene_units_per_mm(mut self, scale: Float) -> Self {
+        self.scene_units_per_mm = scale;
+        self
+    }
+
+    pub fn lens(mut self, lens: Lens) -> Self {
+        self.lens = lens;
+        self
+    }
+
+    pub fn build(self) -> Result<Camera, CameraError> {
+        let view = self.look_at - self.look_from;
+        if view.near_zero() {
+            return Err(CameraError::LookAtEqualsLookFrom);
+        }
+        if self.v_up.near_zero() {
+            return Err(CameraError::ZeroUpVector);
+        }
+        if cross_product(self.v_up, view).len() <= 1e-6 * self.v_up.len() * view.len() {
+            return Err(CameraError::UpParallelToViewDirection);
+        }
+
+        let (sensor_width, sensor_height) = self.sensor_size;
+        // Rejects NaN too, which fails every comparison.
+        let positive = |x: Float| x.is_finite() && x > 0.0;
+        if !positive(sensor_width) || !positive(sensor_height) {
+            return Err(CameraError::InvalidSensorSize(sensor_width, sensor_height));
+        }
+        let aspect_ratio = self.aspect_ratio.unwrap_or(sensor_width / sensor_height);
+        if !positive(aspect_ratio) {
+            return Err(CameraError::InvalidAspectRatio(aspect_ratio));
+        }
+
+        let (vfov, focal_length) = match self.field_of_view {
+            FieldOfView::Vertical(degrees) => {
+                if !positive(degrees) || degrees >= 180.0 {
+                    return Err(CameraError::InvalidFieldOfView(degrees));
+                }
+                let focal_length =
+                    sensor_height / (2.0 * (degrees_to_radians(degrees) / 2.0).tan());
+                (degrees, focal_length)
+            }
+            FieldOfView::FocalLength(millimeters) => {
+                if !positive(millimeters) {
+                    return Err(CameraError::InvalidFocalLength(millimeters));
+                }
+                let radians = 2.0 * (sensor_height / (2.0 * millimeters)).atan();
+                (radians.to_degrees(), millimeters)
+            }
+        };
+
+        let aperture = match self.aperture {
+            ApertureSize::Diameter(diameter) => diameter,
+            ApertureSize::FNumber(f_number) => {
+                if !positive(f_number) {
+                    return Err(CameraError::InvalidAperture(f_number));
+                }
+                focal_length / f_number * self.scene_units_per_mm
+            }
+        };
+        if !(aperture == 0.0 || positive(aperture)) {
+            return Err(CameraError::InvalidAperture(aperture));
+        }
+
+        let focus_distance = match self.focus {
+            Focus::LookAt => view.len(),
+            Focus::Distance(distance) => distance,
+            Focus::Auto(world, time) => {
+                // Cast through the center of the image, which a shifted lens moves off the axis.
+                let (u, v, w) = look_at_basis(self.look_from, self.look_at, self.v_up);
+                let viewport_height = 2.0 * (degrees_to_radians(vfov) / 2.0).tan();
+                let (shift_x, shift_y) = self.lens.shift;
+                let ray = Ray {
+                    origin: self.look_from,
+                    direction: -w
+                        + shift_x * aspect_ratio * viewport_height * u
+                        + shift_y * viewport_height * v,
+                    time,
+                    wavelength: None,
+                };
+                match world.hit(&ray, 0.0, Float::MAX) {
+                    Some(hit_record) => dot_product(self.look_from - hit_record.p, w),
+                    None => view.len(),
+                }
+            }
+        };
+        if !positive(focus_distance) {
+            return Err(CameraError::InvalidFocusDistance(focus_distance));
+        }
+
+        Ok(Camera::new(
+            self.look_from,
+            self.look_at,
+            self.v_up,
+            vfov,
+            aspect_ratio,
+            aperture,
+            focus_distance,
+        )
+        .with_lens(self.lens))
+    }
+}
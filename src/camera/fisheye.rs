@@ -0,0 +1,71 @@
+use crate::{
+    float::Float,
+    helpers::degrees_to_radians,
+    ray::Ray,
+    vec3::{Point3, Vec3},
+};
+
+use super::{look_at_basis, CameraModel};
+
+/// Relation between the angle from the optical axis and the distance from the image center.
+#[derive(Copy, Clone)]
+pub enum FisheyeMapping {
+    /// `r = f * theta`, angles are spaced evenly across the image.
+    Equidistant,
+    /// `r = 2f * sin(theta / 2)`, preserves solid angle.
+    Equisolid,
+}
+
+/// Fisheye lens whose field of view spans the image height. The corners of wide images
+/// extrapolate the mapping beyond that angle.
+#[derive(Copy, Clone)]
+pub struct Fisheye {
+    origin: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    half_fov: Float,
+    aspect_ratio: Float,
+    mapping: FisheyeMapping,
+}
+
+impl Fisheye {
+    pub fn new(
+        look_from: Point3,
+        look_at: Point3,
+        v_up: Vec3,
+        fov: Float,
+        aspect_ratio: Float,
+        mapping: FisheyeMapping,
+    ) -> Fisheye {
+        let (u, v, w) = look_at_basis(look_from, look_at, v_up);
+        Fisheye {
+            origin: look_from,
+            u,
+            v,
+            w,
+            half_fov: degrees_to_radians(fov) / 2.0,
+            aspect_ratio,
+            mapping,
+        }
+    }
+}
+
+impl CameraModel for Fisheye {
+    fn get_ray(&self, u: Float, v: Float) -> Ray {
+        let x = (2.0 * u - 1.0) * self.aspect_ratio;
+        let y = 2.0 * v - 1.0;
+        let r = (x * x + y * y).sqrt();
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => r * self.half_fov,
+            FisheyeMapping::Equisolid => 2.0 * (r * (self.half_fov / 2.0).sin()).min(1.0).asin(),
+        };
+        let (cos_phi, sin_phi) = if r > 0.0 { (x / r, y / r) } else { (1.0, 0.0) };
+        Ray {
+            origin: self.origin,
+            direction: theta.sin() * (cos_phi * self.u + sin_phi * self.v) - theta.cos() * self.w,
+            time: 0.0,
+            wavelength: None,
+        }
+    }
+}
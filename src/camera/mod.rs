@@ -0,0 +1,318 @@
+use std::sync::Arc;
+
+use crate::{
+    float::Float,
+    helpers::{degrees_to_radians, random_float},
+    ray::Ray,
+    vec3::{cross_product, dot_product, unit_vector, Color, Point3, Vec3},
+};
+
+pub use aperture::{Aperture, ApertureMask};
+pub use builder::{CameraBuilder, CameraError};
+pub use fisheye::{Fisheye, FisheyeMapping};
+pub use orthographic::Orthographic;
+pub use panorama::Equirectangular;
+pub use stereo::Stereo;
+
+mod aperture;
+mod builder;
+mod fisheye;
+mod orthographic;
+mod panorama;
+mod stereo;
+
+/// Camera shared by the threads rendering a frame.
+pub type SharedCamera = Arc<dyn CameraModel + Send + Sync>;
+
+/// Maps normalized film coordinates, `u` left to right and `v` bottom to top, both in `[0, 1]`,
+/// to a primary ray.
+pub trait CameraModel {
+    fn get_ray(&self, u: Float, v: Float) -> Ray;
+
+    /// Like `get_ray`, but also returns a per-channel weight for the radiance carried by the
+    /// ray. Models whose rays differ between color channels override this.
+    fn get_sample(&self, u: Float, v: Float) -> (Ray, Color) {
+        (
+            self.get_ray(u, v),
+            Color {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+        )
+    }
+
+    /// Connects a point in the scene to the lens, for integrators tracing paths from the lights.
+    /// Returns `None` when the point is outside the frame or the model does not support it.
+    fn connect(&self, _p: Point3) -> Option<CameraConnection> {
+        None
+    }
+
+    /// Solid angle density with which `get_ray` generates rays along `direction` from a point on
+    /// the lens, for models that support `connect`.
+    fn pdf_direction(&self, _direction: Vec3) -> Float {
+        0.0
+    }
+}
+
+/// Point on the lens through which a point in the scene is seen.
+pub struct CameraConnection {
+    /// Film coordinates the point projects to, as passed to `get_ray`.
+    pub u: Float,
+    pub v: Float,
+    pub lens_point: Point3,
+    /// Viewing direction of the camera.
+    pub normal: Vec3,
+    /// Importance arriving at the film from the point, divided by the density of the lens point.
+    pub importance: Float,
+}
+
+/// Orthonormal `(u, v, w)` frame of a camera at `look_from` facing `look_at`, with `w` pointing
+/// backwards.
+pub fn look_at_basis(look_from: Point3, look_at: Point3, v_up: Vec3) -> (Vec3, Vec3, Vec3) {
+    let w = unit_vector(look_from - look_at);
+    let u = unit_vector(cross_product(v_up, w));
+    let v = cross_product(w, u);
+    (u, v, w)
+}
+
+/// Optical effects of the thin lens beyond plain defocus.
+#[derive(Clone)]
+pub struct Lens {
+    pub aperture: Aperture,
+    /// Horizontal squeeze of an anamorphic lens; values above one make bokeh taller than wide.
+    pub anamorphic_squeeze: Float,
+    /// Rotation of the focal plane around the camera's horizontal axis, in degrees.
+    pub tilt: Float,
+    /// Offset of the image frame, as fractions of its width and height.
+    pub shift: (Float, Float),
+    /// Relative difference in magnification between neighbouring color channels.
+    pub chromatic_aberration: Float,
+}
+
+impl Default for Lens {
+    fn default() -> Lens {
+        Lens {
+            aperture: Aperture::Circular,
+            anamorphic_squeeze: 1.0,
+            tilt: 0.0,
+            shift: (0.0, 0.0),
+            chromatic_aberration: 0.0,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Camera {
+    pub origin: Point3,
+    pub lower_left_corner: Point3,
+    pub horizontal: Vec3,
+    pub vertical: Vec3,
+    w: Vec3,
+    u: Vec3,
+    v: Vec3,
+    lens_radius: Float,
+    focus_distance: Float,
+    focal_plane_normal: Vec3,
+    lens: Lens,
+}
+
+impl Camera {
+    pub fn builder<'a>() -> CameraBuilder<'a> {
+        CameraBuilder::default()
+    }
+
+    pub fn new(
+        look_from: Point3,
+        look_at: Point3,
+        v_up: Vec3,
+        vof: Float,
+        aspect_ratio: Float,
+        aperture: Float,
+        focus_distance: Float,
+    ) -> Camera {
+        let theta = degrees_to_radians(vof);
+        let h = (theta / 2.0).tan();
+        let viewport_height = 2.0 * h;
+        let viewport_width = aspect_ratio * viewport_height;
+
+        let mut camera = Camera {
+            origin: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            lower_left_corner: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            horizontal: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            vertical: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            w: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            u: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            v: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            lens_radius: 0.0,
+            focus_distance,
+            focal_plane_normal: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            lens: Lens::default(),
+        };
+
+        (camera.u, camera.v, camera.w) = look_at_basis(look_from, look_at, v_up);
+
+        camera.origin = look_from;
+        camera.horizontal = focus_distance * viewport_width * camera.u;
+        camera.vertical = focus_distance * viewport_height * camera.v;
+        camera.lower_left_corner = camera.origin
+            - camera.horizontal / 2.0
+            - camera.vertical / 2.0
+            - focus_distance * camera.w;
+        camera.lens_radius = aperture / 2.0;
+        camera.focal_plane_normal = camera.w;
+        return camera;
+    }
+
+    pub fn with_lens(mut self, lens: Lens) -> Camera {
+        let tilt = degrees_to_radians(lens.tilt);
+        self.focal_plane_normal = tilt.cos() * self.w + tilt.sin() * self.v;
+        self.lower_left_corner = self.lower_left_corner
+            + (lens.shift.0 - self.lens.shift.0) * self.horizontal
+            + (lens.shift.1 - self.lens.shift.1) * self.vertical;
+        self.lens = lens;
+        self
+    }
+
+    /// Point in focus seen through film position `(u, v)`, on the possibly tilted focal plane.
+    fn focus_point(&self, u: Float, v: Float) -> Point3 {
+        let direction =
+            self.lower_left_corner + u * self.horizontal + v * self.vertical - self.origin;
+        if self.lens.tilt == 0.0 {
+            return self.origin + direction;
+        }
+        let t = -self.focus_distance * dot_product(self.w, self.focal_plane_normal)
+            / dot_product(direction, self.focal_plane_normal);
+        self.origin + t * direction
+    }
+
+    /// Area of the film frame scaled to unit distance from the lens.
+    fn film_area(&self) -> Float {
+        self.horizontal.len() * self.vertical.len() / (self.focus_distance * self.focus_distance)
+    }
+}
+
+impl CameraModel for Camera {
+    fn get_ray(&self, u: Float, v: Float) -> Ray {
+        let rd = self.lens_radius * self.lens.aperture.sample();
+        let offset = self.u * (rd.x / self.lens.anamorphic_squeeze) + self.v * rd.y;
+        return Ray {
+            origin: self.origin + offset,
+            direction: self.focus_point(u, v) - self.origin - offset,
+            time: 0.0,
+            wavelength: None,
+        };
+    }
+
+    fn get_sample(&self, u: Float, v: Float) -> (Ray, Color) {
+        let white = Color {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        if self.lens.chromatic_aberration == 0.0 {
+            return (self.get_ray(u, v), white);
+        }
+        // Red is magnified the least and blue the most, with green left untouched.
+        let channel = ((random_float() * 3.0) as usize).min(2);
+        let magnification = 1.0 + self.lens.chromatic_aberration * (channel as Float - 1.0);
+        let weight = match channel {
+            0 => Color {
+                x: 3.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            1 => Color {
+                x: 0.0,
+                y: 3.0,
+                z: 0.0,
+            },
+            _ => Color {
+                x: 0.0,
+                y: 0.0,
+                z: 3.0,
+            },
+        };
+        let ray = self.get_ray(
+            0.5 + (u - 0.5) / magnification,
+            0.5 + (v - 0.5) / magnification,
+        );
+        (ray, weight)
+    }
+
+    /// Supported for lenses without tilt or chromatic aberration, whose rays through a film
+    /// position all meet on a focal plane parallel to the film.
+    fn connect(&self, p: Point3) -> Option<CameraConnection> {
+        if self.lens.tilt != 0.0 || self.lens.chromatic_aberration != 0.0 {
+            return None;
+        }
+        let rd = self.lens_radius * self.lens.aperture.sample();
+        let lens_point =
+            self.origin + self.u * (rd.x / self.lens.anamorphic_squeeze) + self.v * rd.y;
+        let direction = p - lens_point;
+        let depth = dot_product(direction, -self.w);
+        if depth <= 0.0 {
+            return None;
+        }
+        let on_film =
+            lens_point + (self.focus_distance / depth) * direction - self.lower_left_corner;
+        let u = dot_product(on_film, self.horizontal) / self.horizontal.len_squared();
+        let v = dot_product(on_film, self.vertical) / self.vertical.len_squared();
+        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return None;
+        }
+        // Lens points are sampled in proportion to the importance they carry, which leaves only
+        // the fall-off across the film.
+        let cos_theta = depth / direction.len();
+        Some(CameraConnection {
+            u,
+            v,
+            lens_point,
+            normal: -self.w,
+            importance: 1.0 / (self.film_area() * cos_theta.powi(4)),
+        })
+    }
+
+    fn pdf_direction(&self, direction: Vec3) -> Float {
+        if self.lens.tilt != 0.0 || self.lens.chromatic_aberration != 0.0 {
+            return 0.0;
+        }
+        let cos_theta = dot_product(unit_vector(direction), -self.w);
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+        1.0 / (self.film_area() * cos_theta.powi(3))
+    }
+}
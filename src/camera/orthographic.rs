@@ -0,0 +1,48 @@
+use crate::{
+    float::Float,
+    ray::Ray,
+    vec3::{Point3, Vec3},
+};
+
+use super::{look_at_basis, CameraModel};
+
+/// Parallel projection: every ray leaves the film plane along the viewing direction.
+#[derive(Copy, Clone)]
+pub struct Orthographic {
+    lower_left_corner: Point3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    direction: Vec3,
+}
+
+impl Orthographic {
+    /// `viewport_height` is the height of the visible region in world units.
+    pub fn new(
+        look_from: Point3,
+        look_at: Point3,
+        v_up: Vec3,
+        viewport_height: Float,
+        aspect_ratio: Float,
+    ) -> Orthographic {
+        let (u, v, w) = look_at_basis(look_from, look_at, v_up);
+        let horizontal = aspect_ratio * viewport_height * u;
+        let vertical = viewport_height * v;
+        Orthographic {
+            lower_left_corner: look_from - horizontal / 2.0 - vertical / 2.0,
+            horizontal,
+            vertical,
+            direction: -w,
+        }
+    }
+}
+
+impl CameraModel for Orthographic {
+    fn get_ray(&self, u: Float, v: Float) -> Ray {
+        Ray {
+            origin: self.lower_left_corner + u * self.horizontal + v * self.vertical,
+            direction: self.direction,
+            time: 0.0,
+            wavelength: None,
+        }
+    }
+}
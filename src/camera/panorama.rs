@@ -0,0 +1,42 @@
+use crate::{
+    float::{consts::PI, Float},
+    ray::Ray,
+    vec3::{Point3, Vec3},
+};
+
+use super::{look_at_basis, CameraModel};
+
+/// Full 360° x 180° latitude-longitude panorama centered on the viewing direction.
+#[derive(Copy, Clone)]
+pub struct Equirectangular {
+    origin: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Equirectangular {
+    pub fn new(look_from: Point3, look_at: Point3, v_up: Vec3) -> Equirectangular {
+        let (u, v, w) = look_at_basis(look_from, look_at, v_up);
+        Equirectangular {
+            origin: look_from,
+            u,
+            v,
+            w,
+        }
+    }
+}
+
+impl CameraModel for Equirectangular {
+    fn get_ray(&self, u: Float, v: Float) -> Ray {
+        let longitude = (u - 0.5) * 2.0 * PI;
+        let latitude = (v - 0.5) * PI;
+        let horizontal = longitude.sin() * self.u - longitude.cos() * self.w;
+        Ray {
+            origin: self.origin,
+            direction: latitude.cos() * horizontal + latitude.sin() * self.v,
+            time: 0.0,
+            wavelength: None,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{
+    float::Float,
+    ray::Ray,
+    vec3::{Point3, Vec3},
+};
+
+use super::{look_at_basis, Camera, CameraModel};
+
+/// Side-by-side stereo pair: the left half of the image is seen by the left eye and the right
+/// half by the right eye. Both eyes look in parallel, so `aspect_ratio` is that of a single eye.
+#[derive(Clone)]
+pub struct Stereo {
+    left: Camera,
+    right: Camera,
+}
+
+impl Stereo {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        look_from: Point3,
+        look_at: Point3,
+        v_up: Vec3,
+        vof: Float,
+        aspect_ratio: Float,
+        aperture: Float,
+        focus_distance: Float,
+        eye_separation: Float,
+    ) -> Stereo {
+        let (u, _, _) = look_at_basis(look_from, look_at, v_up);
+        let offset = eye_separation / 2.0 * u;
+        let eye = |offset: Vec3| {
+            Camera::new(
+                look_from + offset,
+                look_at + offset,
+                v_up,
+                vof,
+                aspect_ratio,
+                aperture,
+                focus_distance,
+            )
+        };
+        Stereo {
+            left: eye(-offset),
+            right: eye(offset),
+        }
+    }
+}
+
+impl CameraModel for Stereo {
+    fn get_ray(&self, u: Float, v: Float) -> Ray {
+        if u < 0.5 {
+            self.left.get_ray(2.0 * u, v)
+        } else {
+            self.right.get_ray(2.0 * u - 1.0, v)
+        }
+    }
+}
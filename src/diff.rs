@@ -0,0 +1,415 @@
+use std::{error, fmt};
+
+use crate::{
+    float::{consts::PI, Float},
+    helpers::clamp,
+    image::{linear_to_srgb, Image},
+    vec3::Color,
+};
+
+/// Pixels per degree of visual angle the FLIP-style metric assumes the images are seen at: a 4K
+/// monitor 70 cm wide, viewed from 70 cm away.
+const PIXELS_PER_DEGREE: Float = 67.0;
+
+/// How far a rendered image is from a reference one.
+pub struct Comparison {
+    /// Root mean squared difference of the sRGB encoded channels, in `[0, 1]`.
+    pub rmse: Float,
+    /// Peak signal to noise ratio in decibels, infinite for identical images.
+    pub psnr: Float,
+    /// Mean structural similarity of the sRGB encoded luma, 1 for identical images.
+    pub ssim: Float,
+    /// Mean of `flip_map`.
+    pub flip: Float,
+    /// Perceived difference of each pixel in `[0, 1]`, after an approximation of the FLIP
+    /// metric: color differences of the images as blurred by the eye, raised where edges or
+    /// points of one are missing from the other. Gray, with the error in every channel.
+    pub flip_map: Image,
+}
+
+impl Comparison {
+    /// `flip_map` in colors running from black where the images agree, through purple and
+    /// orange, to pale yellow where they differ most.
+    pub fn heatmap(&self) -> Image {
+        self.flip_map.map(|c| magma(c.x))
+    }
+}
+
+#[derive(Debug)]
+pub enum DiffError {
+    SizeMismatch {
+        reference: (i32, i32),
+        test: (i32, i32),
+    },
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffError::SizeMismatch { reference, test } => write!(
+                f,
+                "images differ in size, reference is {}x{}, test is {}x{}",
+                reference.0, reference.1, test.0, test.1
+            ),
+        }
+    }
+}
+
+impl error::Error for DiffError {}
+
+/// Compares `test` to `reference`, both holding linear colors as rendered.
+pub fn compare(reference: &Image, test: &Image) -> Result<Comparison, DiffError> {
+    if (reference.width, reference.height) != (test.width, test.height) {
+        return Err(DiffError::SizeMismatch {
+            reference: (reference.width, reference.height),
+            test: (test.width, test.height),
+        });
+    }
+    let encoded_reference = encode(reference);
+    let encoded_test = encode(test);
+    let squared_error = encoded_reference
+        .iter()
+        .zip(&encoded_test)
+        .map(|(a, b)| {
+            let d = *a - *b;
+            d.x * d.x + d.y * d.y + d.z * d.z
+        })
+        .sum::<Float>()
+        / (3 * encoded_reference.len()) as Float;
+    let rmse = squared_error.sqrt();
+    let flip_map = flip(reference, test);
+    Ok(Comparison {
+        rmse,
+        psnr: -20.0 * rmse.log10(),
+        ssim: ssim(
+            reference.width,
+            reference.height,
+            &encoded_reference,
+            &encoded_test,
+        ),
+        flip: mean(flip_map.pixels().iter().map(|c| c.x)),
+        flip_map,
+    })
+}
+
+/// Colors of `image` clipped to `[0, 1]` and encoded with the sRGB transfer curve, as they
+/// are displayed.
+fn encode(image: &Image) -> Vec<Color> {
+    image
+        .pixels()
+        .iter()
+        .map(|c| Color {
+            x: linear_to_srgb(clamp(c.x, 0.0, 1.0)),
+            y: linear_to_srgb(clamp(c.y, 0.0, 1.0)),
+            z: linear_to_srgb(clamp(c.z, 0.0, 1.0)),
+        })
+        .collect()
+}
+
+/// Structural similarity of the luma of the encoded colors `a` and `b`, with the usual Gaussian
+/// window of standard deviation 1.5 pixels.
+fn ssim(width: i32, height: i32, a: &[Color], b: &[Color]) -> Float {
+    const C1: Float = 0.01 * 0.01;
+    const C2: Float = 0.03 * 0.03;
+    let luma = |colors: &[Color]| -> Vec<Float> {
+        colors
+            .iter()
+            .map(|c| 0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z)
+            .collect()
+    };
+    let (x, y) = (luma(a), luma(b));
+    let window = gaussian(1.5);
+    let blur = |values: Vec<Float>| convolve(width, height, &values, &window, &window);
+    let mean_x = blur(x.clone());
+    let mean_y = blur(y.clone());
+    let xx = blur(x.iter().map(|v| v * v).collect());
+    let yy = blur(y.iter().map(|v| v * v).collect());
+    let xy = blur(x.iter().zip(&y).map(|(u, v)| u * v).collect());
+    mean((0..x.len()).map(|i| {
+        let (mx, my) = (mean_x[i], mean_y[i]);
+        let variance_x = xx[i] - mx * mx;
+        let variance_y = yy[i] - my * my;
+        let covariance = xy[i] - mx * my;
+        ((2.0 * mx * my + C1) * (2.0 * covariance + C2))
+            / ((mx * mx + my * my + C1) * (variance_x + variance_y + C2))
+    }))
+}
+
+/// Per-pixel error map of a simplified FLIP: the images are filtered like the contrast
+/// sensitivity of the eye filters them, their colors compared in L*a*b*, and the color error
+/// raised towards 1 where their edges and points differ.
+fn flip(reference: &Image, test: &Image) -> Image {
+    let (width, height) = (reference.width, reference.height);
+    let color_error_max = hyab(lab(GREEN), lab(BLUE)).powf(0.7);
+    let filtered_reference = filter_opponent(reference);
+    let filtered_test = filter_opponent(test);
+    let (edges_reference, points_reference) = features(reference);
+    let (edges_test, points_test) = features(test);
+    let pixels = (0..filtered_reference.len())
+        .map(|i| {
+            let color_error = (hyab(filtered_reference[i], filtered_test[i]).powf(0.7)
+                / color_error_max)
+                .min(1.0);
+            let feature_difference = (edges_reference[i] - edges_test[i])
+                .abs()
+                .max((points_reference[i] - points_test[i]).abs());
+            let feature_error = (feature_difference / (2.0 as Float).sqrt()).min(1.0).sqrt();
+            let error = color_error.powf(1.0 - feature_error);
+            Color {
+                x: error,
+                y: error,
+                z: error,
+            }
+        })
+        .collect();
+    Image::from_pixels(width, height, pixels)
+}
+
+/// L*a*b* colors of `image` after blurring its achromatic and two chromatic opponent channels
+/// by the spread of the contrast sensitivity of each.
+fn filter_opponent(image: &Image) -> Vec<Color> {
+    let (width, height) = (image.width, image.height);
+    let opponent: Vec<Color> = image
+        .pixels()
+        .iter()
+        .map(|c| linear_to_ycxcz(clamp_color(*c)))
+        .collect();
+    let channel = |select: fn(&Color) -> Float, spread: Float| {
+        // Width in degrees² of the Gaussian FLIP models each channel's sensitivity with.
+        let sigma = (spread / (2.0 * PI * PI)).sqrt() * PIXELS_PER_DEGREE;
+        let kernel = gaussian(sigma);
+        let values: Vec<Float> = opponent.iter().map(select).collect();
+        convolve(width, height, &values, &kernel, &kernel)
+    };
+    let y = channel(|c| c.x, 0.0047);
+    let cx = channel(|c| c.y, 0.0053);
+    let cz = channel(|c| c.z, 0.04);
+    (0..opponent.len())
+        .map(|i| {
+            lab(clamp_color(ycxcz_to_linear(Color {
+                x: y[i],
+                y: cx[i],
+                z: cz[i],
+            })))
+        })
+        .collect()
+}
+
+/// Strength of the edges and of the points at every pixel of `image`, from the first and
+/// second derivatives of its lightness blurred to the size of the features the eye picks out.
+fn features(image: &Image) -> (Vec<Float>, Vec<Float>) {
+    let (width, height) = (image.width, image.height);
+    let lightness: Vec<Float> = image
+        .pixels()
+        .iter()
+        .map(|c| lab(clamp_color(*c)).x / 100.0)
+        .collect();
+    let sigma = 0.5 * 0.082 * PIXELS_PER_DEGREE;
+    let smooth = gaussian(sigma);
+    let first = derivative(sigma, 1);
+    let second = derivative(sigma, 2);
+    let magnitude = |x: Vec<Float>, y: Vec<Float>| -> Vec<Float> {
+        x.iter().zip(&y).map(|(a, b)| a.hypot(*b)).collect()
+    };
+    let edges = magnitude(
+        convolve(width, height, &lightness, &first, &smooth),
+        convolve(width, height, &lightness, &smooth, &first),
+    );
+    let points = magnitude(
+        convolve(width, height, &lightness, &second, &smooth),
+        convolve(width, height, &lightness, &smooth, &second),
+    );
+    (edges, points)
+}
+
+/// Normalized Gaussian kernel reaching three standard deviations out.
+fn gaussian(sigma: Float) -> Vec<Float> {
+    let radius = (3.0 * sigma).ceil().max(1.0) as i32;
+    let weights: Vec<Float> = (-radius..=radius)
+        .map(|x| (-((x * x) as Float) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: Float = weights.iter().sum();
+    weights.iter().map(|w| w / total).collect()
+}
+
+/// Kernel of the first or second derivative of a Gaussian, scaled so its positive and its
+/// negative weights each add up to one in magnitude.
+fn derivative(sigma: Float, order: i32) -> Vec<Float> {
+    let radius = (3.0 * sigma).ceil() as i32;
+    let weights: Vec<Float> = (-radius..=radius)
+        .map(|x| {
+            let x = x as Float;
+            let g = (-x * x / (2.0 * sigma * sigma)).exp();
+            if order == 1 {
+                -x * g
+            } else {
+                (x * x / (sigma * sigma) - 1.0) * g
+            }
+        })
+        .collect();
+    let positive: Float = weights.iter().filter(|w| **w > 0.0).sum();
+    let negative: Float = -weights.iter().filter(|w| **w < 0.0).sum::<Float>();
+    weights
+        .iter()
+        .map(|w| if *w > 0.0 { w / positive } else { w / negative })
+        .collect()
+}
+
+/// Convolves the `width` by `height` grid of `values` with `horizontal` along its rows and
+/// `vertical` along its columns, repeating the pixels at its borders.
+fn convolve(
+    width: i32,
+    height: i32,
+    values: &[Float],
+    horizontal: &[Float],
+    vertical: &[Float],
+) -> Vec<Float> {
+    let pass = |values: &[Float], kernel: &[Float], step_x: i32, step_y: i32| -> Vec<Float> {
+        let radius = (kernel.len() / 2) as i32;
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(k, weight)| {
+                        let offset = k as i32 - radius;
+                        let sx = (x + offset * step_x).clamp(0, width - 1);
+                        let sy = (y + offset * step_y).clamp(0, height - 1);
+                        weight * values[(sy * width + sx) as usize]
+                    })
+                    .sum()
+            })
+            .collect()
+    };
+    let rows = pass(values, horizontal, 1, 0);
+    pass(&rows, vertical, 0, 1)
+}
+
+fn mean(values: impl Iterator<Item = Float>) -> Float {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    sum / count as Float
+}
+
+fn clamp_color(c: Color) -> Color {
+    Color {
+        x: clamp(c.x, 0.0, 1.0),
+        y: clamp(c.y, 0.0, 1.0),
+        z: clamp(c.z, 0.0, 1.0),
+    }
+}
+
+const GREEN: Color = Color {
+    x: 0.0,
+    y: 1.0,
+    z: 0.0,
+};
+
+const BLUE: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 1.0,
+};
+
+/// CIE XYZ of the D65 white point, which linear sRGB white maps to.
+const WHITE: Color = Color {
+    x: 0.950_47,
+    y: 1.0,
+    z: 1.088_83,
+};
+
+fn linear_to_xyz(c: Color) -> Color {
+    Color {
+        x: 0.412_456_4 * c.x + 0.357_576_1 * c.y + 0.180_437_5 * c.z,
+        y: 0.212_672_9 * c.x + 0.715_152_2 * c.y + 0.072_175 * c.z,
+        z: 0.019_333_9 * c.x + 0.119_192 * c.y + 0.950_304_1 * c.z,
+    }
+}
+
+fn xyz_to_linear(c: Color) -> Color {
+    Color {
+        x: 3.240_454_2 * c.x - 1.537_138_5 * c.y - 0.498_531_4 * c.z,
+        y: -0.969_266 * c.x + 1.876_010_8 * c.y + 0.041_556 * c.z,
+        z: 0.055_643_4 * c.x - 0.204_025_9 * c.y + 1.057_225_2 * c.z,
+    }
+}
+
+/// Opponent color space of FLIP: lightness, red-green and blue-yellow.
+fn linear_to_ycxcz(c: Color) -> Color {
+    let xyz = linear_to_xyz(c);
+    let (x, y, z) = (xyz.x / WHITE.x, xyz.y / WHITE.y, xyz.z / WHITE.z);
+    Color {
+        x: 116.0 * y - 16.0,
+        y: 500.0 * (x - y),
+        z: 200.0 * (y - z),
+    }
+}
+
+fn ycxcz_to_linear(c: Color) -> Color {
+    let y = (c.x + 16.0) / 116.0;
+    xyz_to_linear(Color {
+        x: (y + c.y / 500.0) * WHITE.x,
+        y: y * WHITE.y,
+        z: (y - c.z / 200.0) * WHITE.z,
+    })
+}
+
+/// CIE L*a*b* of a linear sRGB color.
+fn lab(c: Color) -> Color {
+    let xyz = linear_to_xyz(c);
+    let f = |t: Float| {
+        let delta: Float = 6.0 / 29.0;
+        if t > delta * delta * delta {
+            t.cbrt()
+        } else {
+            t / (3.0 * delta * delta) + 4.0 / 29.0
+        }
+    };
+    let (fx, fy, fz) = (f(xyz.x / WHITE.x), f(xyz.y / WHITE.y), f(xyz.z / WHITE.z));
+    Color {
+        x: 116.0 * fy - 16.0,
+        y: 500.0 * (fx - fy),
+        z: 200.0 * (fy - fz),
+    }
+}
+
+/// Distance between L*a*b* colors that weighs lightness and chroma apart, suited to large
+/// differences.
+fn hyab(a: Color, b: Color) -> Float {
+    (a.x - b.x).abs() + (a.y - b.y).hypot(a.z - b.z)
+}
+
+/// Magma color map, from black at 0 to pale yellow at 1, interpolated between five stops.
+fn magma(t: Float) -> Color {
+    const STOPS: [Color; 5] = [
+        Color {
+            x: 0.001,
+            y: 0.0,
+            z: 0.014,
+        },
+        Color {
+            x: 0.316,
+            y: 0.071,
+            z: 0.485,
+        },
+        Color {
+            x: 0.716,
+            y: 0.215,
+            z: 0.475,
+        },
+        Color {
+            x: 0.987,
+            y: 0.535,
+            z: 0.383,
+        },
+        Color {
+            x: 0.987,
+            y: 0.991,
+            z: 0.75,
+        },
+    ];
+    let position = clamp(t, 0.0, 1.0) * (STOPS.len() - 1) as Float;
+    let index = (position as usize).min(STOPS.len() - 2);
+    let f = position - index as Float;
+    (1.0 - f) * STOPS[index] + f * STOPS[index + 1]
+}
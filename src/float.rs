@@ -0,0 +1,19 @@
+//! Scalar type of all the math in the renderer: `f32`, or `f64` with the `f64` feature for
+//! scenes large enough that single precision shows.
+
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts;
+#[cfg(feature = "f64")]
+pub use std::f64::consts;
+
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+/// Bound on the relative error accumulated by `n` rounded operations, as derived in Physically
+/// Based Rendering.
+pub fn gamma(n: i32) -> Float {
+    let epsilon = n as Float * Float::EPSILON * 0.5;
+    epsilon / (1.0 - epsilon)
+}
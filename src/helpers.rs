@@ -1,22 +1,38 @@
-use crate::vec3::{dot_product, Vec3};
+use std::cell::RefCell;
 
-pub fn degrees_to_radians(degrees: f32) -> f32 {
-    degrees * std::f32::consts::PI / 180.0
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    float::{consts::PI, Float},
+    vec3::Vec3,
+};
+
+pub fn degrees_to_radians(degrees: Float) -> Float {
+    degrees * PI / 180.0
+}
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Restarts the random numbers drawn on this thread from `seed`, so they repeat from run to run.
+pub fn seed_random(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
 }
 
-pub fn random_f32() -> f32 {
-    rand::random()
+pub fn random_float() -> Float {
+    RNG.with(|rng| rng.borrow_mut().gen())
 }
 
-pub fn random_f32_in_range(min: f32, max: f32) -> f32 {
-    min + (max - min) * rand::random::<f32>()
+pub fn random_float_in_range(min: Float, max: Float) -> Float {
+    min + (max - min) * random_float()
 }
 
-pub fn random_vec3(min: f32, max: f32) -> Vec3 {
+pub fn random_vec3(min: Float, max: Float) -> Vec3 {
     Vec3 {
-        x: random_f32_in_range(min, max),
-        y: random_f32_in_range(min, max),
-        z: random_f32_in_range(min, max),
+        x: random_float_in_range(min, max),
+        y: random_float_in_range(min, max),
+        z: random_float_in_range(min, max),
     }
 }
 
@@ -33,8 +49,8 @@ pub fn random_in_unit_sphere() -> Vec3 {
 pub fn random_in_unit_disk() -> Vec3 {
     loop {
         let p = Vec3 {
-            x: random_f32_in_range(-1.0, 1.0),
-            y: random_f32_in_range(-1.0, 1.0),
+            x: random_float_in_range(-1.0, 1.0),
+            y: random_float_in_range(-1.0, 1.0),
             z: 0.0,
         };
         if p.len_squared() >= 1.0 {
@@ -44,16 +60,7 @@ pub fn random_in_unit_disk() -> Vec3 {
     }
 }
 
-pub fn random_in_hemisphere(normal: Vec3) -> Vec3 {
-    let in_unit_sphere = random_in_unit_sphere();
-    if dot_product(in_unit_sphere, normal) > 0.0 {
-        return in_unit_sphere;
-    } else {
-        return -in_unit_sphere;
-    }
-}
-
-pub fn clamp(x: f32, min: f32, max: f32) -> f32 {
+pub fn clamp(x: Float, min: Float, max: Float) -> Float {
     if x < min {
         return min;
     } else if x > max {
@@ -0,0 +1,59 @@
+use crate::{
+    float::Float, helpers::random_float, ray::Ray, texture::SharedTexture, vec3::dot_product,
+};
+
+use super::{Aabb, BoxedHittable, HitRecord, Hittable};
+
+#[derive(Copy, Clone)]
+pub enum AlphaMode {
+    /// Surfaces with opacity below the cutoff are skipped, the rest are fully opaque.
+    Threshold(Float),
+    /// Each intersection is kept with probability equal to the opacity, which converges to a
+    /// partially transparent surface.
+    Stochastic,
+}
+
+/// Cuts holes into `object` where the luminance of `opacity` at the hit point is low. Rays go
+/// through the holes as if the surface was not there, without a scatter event.
+pub struct AlphaMasked {
+    pub object: BoxedHittable,
+    pub opacity: SharedTexture,
+    pub mode: AlphaMode,
+}
+
+impl AlphaMasked {
+    fn is_opaque(&self, hit_record: &HitRecord) -> bool {
+        let c = self.opacity.value(hit_record);
+        let alpha = 0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z;
+        match self.mode {
+            AlphaMode::Threshold(cutoff) => alpha >= cutoff,
+            AlphaMode::Stochastic => random_float() < alpha,
+        }
+    }
+}
+
+impl Hittable for AlphaMasked {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut current = *ray;
+        let mut t_min = t_min;
+        // Parameter along `ray` of the origin of `current`.
+        let mut t_origin = 0.0;
+        loop {
+            let mut hit_record = self.object.hit(&current, t_min, t_max - t_origin)?;
+            if self.is_opaque(&hit_record) {
+                hit_record.t += t_origin;
+                return Some(hit_record);
+            }
+            // Look for the next intersection behind the transparent one, e.g. the far side of a
+            // masked sphere, from just past the surface as rays leaving it do.
+            current = hit_record.spawn_ray(&current, current.direction);
+            t_origin = dot_product(current.origin - ray.origin, ray.direction)
+                / ray.direction.len_squared();
+            t_min = 0.0;
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box()
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{
+    animation::Transform,
+    float::{gamma, Float},
+    ray::Ray,
+    vec3::{Point3, Vec3},
+};
+
+use super::{bvh::Aabb, BoxedHittable, HitRecord, Hittable};
+
+/// Moves, turns and scales `object` according to `transform`, posed at the time carried by each
+/// ray.
+pub struct Animated {
+    pub object: BoxedHittable,
+    pub transform: Transform,
+}
+
+impl Hittable for Animated {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let pose = self.transform.at(ray.time);
+        let local_ray = Ray {
+            origin: pose.point_to_local(ray.origin),
+            direction: pose.vector_to_local(ray.direction),
+            time: ray.time,
+            wavelength: ray.wavelength,
+        };
+        let mut hit_record = self.object.hit(&local_ray, t_min, t_max)?;
+        let scaled = pose.scale * hit_record.p;
+        let rotated = pose.rotate(scaled);
+        hit_record.p = rotated + pose.translation;
+        // The rounding of the transform, once here and once more when rays leaving the object
+        // are brought back to its local space. Each of the three turns rounds twice.
+        hit_record.p_error = pose.rotate_bound(pose.scale.abs() * hit_record.p_error)
+            + gamma(6) * pose.rotate_bound(scaled.abs())
+            + gamma(4) * (rotated.abs() + pose.translation.abs() + hit_record.p.abs());
+        hit_record.normal = pose.normal_to_world(hit_record.normal);
+        hit_record.geometric_normal = pose.normal_to_world(hit_record.geometric_normal);
+        hit_record.dpdu = pose.vector_to_world(hit_record.dpdu);
+        hit_record.dpdv = pose.vector_to_world(hit_record.dpdv);
+        Some(hit_record)
+    }
+
+    /// Box around the object in every pose it can take. The tracks stay within the hulls of
+    /// their control points; while the object turns, it stays within the sphere around its
+    /// origin that holds its box.
+    fn bounding_box(&self) -> Option<Aabb> {
+        let local = self.object.bounding_box()?;
+        let corners: Vec<Point3> = (0..8)
+            .map(|i| Point3 {
+                x: if i & 1 == 0 { local.min.x } else { local.max.x },
+                y: if i & 2 == 0 { local.min.y } else { local.max.y },
+                z: if i & 4 == 0 { local.min.z } else { local.max.z },
+            })
+            .collect();
+        let angles = self.transform.rotation.control_points();
+        let turned: Vec<Point3> = if angles.iter().all(|a| (*a - angles[0]).near_zero()) {
+            let pose = self
+                .transform
+                .at(self.transform.rotation.keyframes()[0].time);
+            corners.iter().map(|c| pose.rotate(*c)).collect()
+        } else {
+            let radius = corners.iter().map(|c| c.len()).fold(0.0, Float::max);
+            let extent = Vec3 {
+                x: radius,
+                y: radius,
+                z: radius,
+            };
+            vec![-extent, extent]
+        };
+        let scales = self.transform.scale.control_points();
+        let scaled: Vec<Point3> = turned
+            .iter()
+            .flat_map(|p| scales.iter().map(move |s| *s * *p))
+            .collect();
+        let shape = Aabb::around(&scaled);
+        let offsets = Aabb::around(&self.transform.translation.control_points());
+        Some(Aabb {
+            min: shape.min + offsets.min,
+            max: shape.max + offsets.max,
+        })
+    }
+}
@@ -0,0 +1,330 @@
+use std::cmp::Ordering;
+
+use crate::{
+    float::Float,
+    ray::Ray,
+    simd::Floatx4,
+    vec3::{Point3, Vec3},
+};
+
+use super::{packet::RayPacket, BoxedHittable, HitRecord, Hittable};
+
+/// Most objects stored in a single leaf of a `Bvh`.
+const LEAF_SIZE: usize = 4;
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    /// Smallest box containing all of `points`.
+    pub fn around(points: &[Point3]) -> Aabb {
+        let mut min = Point3 {
+            x: Float::MAX,
+            y: Float::MAX,
+            z: Float::MAX,
+        };
+        let mut max = -min;
+        for p in points {
+            min = Point3 {
+                x: min.x.min(p.x),
+                y: min.y.min(p.y),
+                z: min.z.min(p.z),
+            };
+            max = Point3 {
+                x: max.x.max(p.x),
+                y: max.y.max(p.y),
+                z: max.z.max(p.z),
+            };
+        }
+        Aabb { min, max }
+    }
+
+    pub fn union(self, other: Aabb) -> Aabb {
+        Aabb::around(&[self.min, self.max, other.min, other.max])
+    }
+
+    fn centroid(&self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    /// Whether the ray from `origin` with the reciprocal of its direction `inverse_direction`
+    /// passes through the box between `t_min` and `t_max`.
+    fn hit(&self, origin: Point3, inverse_direction: Vec3, t_min: Float, t_max: Float) -> bool {
+        let mut t_near = t_min;
+        let mut t_far = t_max;
+        for axis in 0..3 {
+            let inverse = component(inverse_direction, axis);
+            let t0 = (component(self.min, axis) - component(origin, axis)) * inverse;
+            let t1 = (component(self.max, axis) - component(origin, axis)) * inverse;
+            // Written so that NaNs, from rays parallel to a face lying in its plane, keep the
+            // interval unchanged.
+            t_near = if t0 < t1 {
+                t0.max(t_near)
+            } else {
+                t1.max(t_near)
+            };
+            t_far = if t0 < t1 {
+                t1.min(t_far)
+            } else {
+                t0.min(t_far)
+            };
+        }
+        t_near <= t_far
+    }
+
+    /// Mask of the lanes of group `group` of `packet` passing through the box between `t_min`
+    /// and their `t_max`.
+    fn hit_packet(&self, packet: &RayPacket, group: usize, t_min: Floatx4, t_max: Floatx4) -> u32 {
+        let origin = packet.origins[group];
+        let inverse = packet.inverse_directions[group];
+        let slab = |min: Float, max: Float, origin: Floatx4, inverse: Floatx4| {
+            let t0 = (Floatx4::splat(min) - origin) * inverse;
+            let t1 = (Floatx4::splat(max) - origin) * inverse;
+            (t0.min(t1), t0.max(t1))
+        };
+        let (x0, x1) = slab(self.min.x, self.max.x, origin.x, inverse.x);
+        let (y0, y1) = slab(self.min.y, self.max.y, origin.y, inverse.y);
+        let (z0, z1) = slab(self.min.z, self.max.z, origin.z, inverse.z);
+        // `min` and `max` return their second operand for NaN lanes, which keeps those slabs
+        // from narrowing the interval.
+        let t_near = x0.max(t_min);
+        let t_near = y0.max(t_near);
+        let t_near = z0.max(t_near);
+        let t_far = x1.min(t_max);
+        let t_far = y1.min(t_far);
+        let t_far = z1.min(t_far);
+        t_near.le(t_far).bits()
+    }
+}
+
+enum Node {
+    Leaf {
+        bounds: Aabb,
+        first: usize,
+        count: usize,
+    },
+    Interior {
+        bounds: Aabb,
+        /// The second child directly follows its subtree in `Bvh::nodes`.
+        second: usize,
+        axis: usize,
+    },
+}
+
+impl Node {
+    fn bounds(&self) -> &Aabb {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// Bounding volume hierarchy over the indices of objects of a scene, split at the median of
+/// their centroids along the axis they are most spread over. Objects without a bounding box
+/// are tested one by one besides it.
+pub struct Bvh {
+    /// Depth first order, each interior node followed by its first child.
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(objects: &[BoxedHittable]) -> Bvh {
+        let mut bounded = vec![];
+        let mut unbounded = vec![];
+        for (index, object) in objects.iter().enumerate() {
+            match object.bounding_box() {
+                Some(bounds) => bounded.push((index, bounds)),
+                None => unbounded.push(index),
+            }
+        }
+        let mut bvh = Bvh {
+            nodes: vec![],
+            indices: vec![],
+            unbounded,
+        };
+        if !bounded.is_empty() {
+            bvh.build(&mut bounded);
+        }
+        bvh
+    }
+
+    fn build(&mut self, objects: &mut [(usize, Aabb)]) {
+        let bounds = objects
+            .iter()
+            .skip(1)
+            .fold(objects[0].1, |bounds, (_, b)| bounds.union(*b));
+        if objects.len() <= LEAF_SIZE {
+            self.nodes.push(Node::Leaf {
+                bounds,
+                first: self.indices.len(),
+                count: objects.len(),
+            });
+            self.indices.extend(objects.iter().map(|(index, _)| *index));
+            return;
+        }
+        let centroids = Aabb::around(
+            &objects
+                .iter()
+                .map(|(_, b)| b.centroid())
+                .collect::<Vec<_>>(),
+        );
+        let extent = centroids.max - centroids.min;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+        let middle = objects.len() / 2;
+        objects.select_nth_unstable_by(middle, |(_, a), (_, b)| {
+            component(a.centroid(), axis)
+                .partial_cmp(&component(b.centroid(), axis))
+                .unwrap_or(Ordering::Equal)
+        });
+        let node = self.nodes.len();
+        self.nodes.push(Node::Interior {
+            bounds,
+            second: 0,
+            axis,
+        });
+        let (first, second) = objects.split_at_mut(middle);
+        self.build(first);
+        let second_index = self.nodes.len();
+        if let Node::Interior { second, .. } = &mut self.nodes[node] {
+            *second = second_index;
+        }
+        self.build(second);
+    }
+
+    /// Closest hit of `ray` with `objects`, the slice the hierarchy was built over, reporting
+    /// the index of the object hit as its `object_id`.
+    pub fn hit(
+        &self,
+        objects: &[BoxedHittable],
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+    ) -> Option<HitRecord> {
+        let mut closest: Option<HitRecord> = None;
+        let mut t_max = t_max;
+        let mut test = |index: usize, t_max: &mut Float| {
+            if let Some(mut hit_record) = objects[index].hit(ray, t_min, *t_max) {
+                *t_max = hit_record.t;
+                hit_record.object_id = index;
+                closest = Some(hit_record);
+            }
+        };
+        for index in self.unbounded.iter() {
+            test(*index, &mut t_max);
+        }
+        if self.nodes.is_empty() {
+            return closest;
+        }
+        let inverse_direction = Vec3 {
+            x: 1.0 / ray.direction.x,
+            y: 1.0 / ray.direction.y,
+            z: 1.0 / ray.direction.z,
+        };
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            if !self.nodes[node]
+                .bounds()
+                .hit(ray.origin, inverse_direction, t_min, t_max)
+            {
+                continue;
+            }
+            match self.nodes[node] {
+                Node::Leaf { first, count, .. } => {
+                    for index in self.indices[first..first + count].iter() {
+                        test(*index, &mut t_max);
+                    }
+                }
+                Node::Interior { second, axis, .. } => {
+                    // Visit the child nearer to the ray's origin first, so later boxes can be
+                    // culled by the hits found in it.
+                    if component(ray.direction, axis) < 0.0 {
+                        stack.push(node + 1);
+                        stack.push(second);
+                    } else {
+                        stack.push(second);
+                        stack.push(node + 1);
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    /// Closest hits of all rays of `packet`, traversing the hierarchy once for the whole packet
+    /// and skipping the nodes none of its rays pass through.
+    pub fn hit_packet(
+        &self,
+        objects: &[BoxedHittable],
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: Float,
+    ) -> Vec<Option<HitRecord>> {
+        let mut hits = vec![None; packet.rays.len()];
+        let test = |index: usize, lanes: u32, hits: &mut [Option<HitRecord>]| {
+            let updated = objects[index].hit_packet(packet, t_min, t_max, lanes, hits);
+            for (lane, hit_record) in hits.iter_mut().enumerate() {
+                if let (true, Some(hit_record)) = (updated & (1 << lane) != 0, hit_record) {
+                    hit_record.object_id = index;
+                }
+            }
+        };
+        for index in self.unbounded.iter() {
+            test(*index, packet.all(), &mut hits);
+        }
+        if self.nodes.is_empty() {
+            return hits;
+        }
+        let t_min_lanes = Floatx4::splat(t_min);
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let bounds = self.nodes[node].bounds();
+            let lanes = (0..packet.origins.len())
+                .map(|group| {
+                    let t_max = packet.t_max(group, t_max, &hits);
+                    bounds.hit_packet(packet, group, t_min_lanes, t_max) << (4 * group)
+                })
+                .fold(0, |lanes, group| lanes | group)
+                & packet.all();
+            if lanes == 0 {
+                continue;
+            }
+            match self.nodes[node] {
+                Node::Leaf { first, count, .. } => {
+                    for index in self.indices[first..first + count].iter() {
+                        test(*index, lanes, &mut hits);
+                    }
+                }
+                Node::Interior { second, axis, .. } => {
+                    if component(packet.rays[0].direction, axis) < 0.0 {
+                        stack.push(node + 1);
+                        stack.push(second);
+                    } else {
+                        stack.push(second);
+                        stack.push(node + 1);
+                    }
+                }
+            }
+        }
+        hits
+    }
+}
+
+fn component(v: Vec3, axis: usize) -> Float {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
@@ -1,17 +1,48 @@
 use std::sync::Arc;
 
 use crate::{
-    material::Material,
-    ray::Ray,
-    vec3::{dot_product, Point3, Vec3},
+    float::{consts::PI, gamma, Float},
+    helpers::random_in_unit_sphere,
+    material::SharedMaterial,
+    ray::{offset_origin, Ray},
+    simd::{Floatx4, Vec3x4},
+    vec3::{dot_product, unit_vector, Point3, Vec3},
 };
 
+pub use alpha_masked::{AlphaMasked, AlphaMode};
+pub use animated::Animated;
+pub use bvh::Aabb;
+pub use packet::{hit_each, RayPacket};
+pub use quad::Quad;
+pub use world::World;
+
+mod alpha_masked;
+mod animated;
+mod bvh;
+mod packet;
+mod quad;
+mod world;
+
+#[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
-    pub t: f32,
+    pub t: Float,
     pub front_face: bool,
-    pub material: Option<Arc<Box<dyn Material + Send + Sync>>>,
+    pub material: Option<SharedMaterial>,
+    pub object_id: usize,
+    /// Surface parameterization at the hit point, used for texturing.
+    pub u: Float,
+    pub v: Float,
+    /// Partial derivatives of the hit point with respect to `u` and `v`, spanning the tangent
+    /// plane.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// Bound on the absolute rounding error of each coordinate of `p`.
+    pub p_error: Vec3,
+    /// Normal of the surface itself, which unlike `normal` is never replaced by materials
+    /// shading with a perturbed one.
+    pub geometric_normal: Vec3,
 }
 impl HitRecord {
     fn set_face_normal(&mut self, ray: &Ray, outward_normal: Vec3) {
@@ -25,10 +56,12 @@ impl HitRecord {
 
     fn new(
         p: Point3,
-        t: f32,
+        t: Float,
         ray: &Ray,
         outward_normal: Vec3,
-        material: Arc<Box<dyn Material + Send + Sync>>,
+        material: SharedMaterial,
+        (u, v): (Float, Float),
+        (dpdu, dpdv): (Vec3, Vec3),
     ) -> HitRecord {
         let mut rec = HitRecord {
             p,
@@ -36,24 +69,137 @@ impl HitRecord {
             t,
             front_face: false,
             material: Some(material),
+            object_id: 0,
+            u,
+            v,
+            dpdu,
+            dpdv,
+            p_error: ZERO,
+            geometric_normal: outward_normal,
         };
         rec.set_face_normal(ray, outward_normal);
         return rec;
     }
+
+    /// Record of a point picked on a surface rather than found by a ray, seen from the side
+    /// `outward_normal` points to.
+    fn on_surface(
+        p: Point3,
+        outward_normal: Vec3,
+        material: SharedMaterial,
+        (u, v): (Float, Float),
+        (dpdu, dpdv): (Vec3, Vec3),
+    ) -> HitRecord {
+        HitRecord {
+            p,
+            normal: outward_normal,
+            t: 0.0,
+            front_face: true,
+            material: Some(material),
+            object_id: 0,
+            u,
+            v,
+            dpdu,
+            dpdv,
+            p_error: ZERO,
+            geometric_normal: outward_normal,
+        }
+    }
+
+    /// Origin for rays leaving the surface in direction `w`, see `offset_origin`.
+    pub fn spawn_origin(&self, w: Vec3) -> Point3 {
+        offset_origin(self.p, self.p_error, self.geometric_normal, w)
+    }
+
+    /// Ray leaving the surface in `direction`, at the time and wavelength of `ray`.
+    pub fn spawn_ray(&self, ray: &Ray, direction: Vec3) -> Ray {
+        Ray {
+            origin: self.spawn_origin(direction),
+            direction,
+            time: ray.time,
+            wavelength: ray.wavelength,
+        }
+    }
 }
 
+/// Object of a scene, owned by the `World` or by the object wrapping it.
+pub type BoxedHittable = Box<dyn Hittable + Sync + Send>;
+
 pub trait Hittable {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+    /// Closest intersection along `ray` with `t` in `(t_min, t_max]`. The interval is open at
+    /// `t_min` so rays spawned right on a surface, at zero, do not hit it again.
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord>;
+
+    /// Picks a point uniformly over the surface, returned as a front facing record together with
+    /// its density per unit area. Only shapes that can serve as area lights implement this.
+    fn sample_surface(&self) -> Option<(HitRecord, Float)> {
+        None
+    }
+
+    /// Box enclosing the object at all times, or `None` when it has no finite bounds or they
+    /// are not known. Objects with a box are sorted into the `Bvh` of the scene.
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+
+    /// Intersects the rays of `packet` selected by the bits of `lanes`, replacing the entries of
+    /// `hits` the object is closer than. Returns the mask of the lanes it replaced, which must
+    /// match what calling `hit` on each ray would have found.
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: Float,
+        lanes: u32,
+        hits: &mut [Option<HitRecord>],
+    ) -> u32 {
+        hit_each(self, packet, t_min, t_max, lanes, hits)
+    }
 }
 
 pub struct Sphere {
     pub center: Point3,
-    pub radius: f32,
-    pub material: Arc<Box<dyn Material + Send + Sync>>,
+    pub radius: Float,
+    pub material: SharedMaterial,
+}
+
+impl Sphere {
+    /// Point on the surface in the unit `direction` from the center, together with a bound on its
+    /// rounding error. Points are projected onto the sphere rather than taken from along a ray,
+    /// whose error grows with the distance travelled.
+    fn surface_point(&self, direction: Vec3) -> (Point3, Vec3) {
+        let offset = self.radius.abs() * direction;
+        let p = self.center + offset;
+        (p, gamma(5) * offset.abs() + gamma(1) * p.abs())
+    }
+
+    /// Latitude-longitude `(u, v)` of a point in the unit direction `n` from the center, `v`
+    /// running from the south to the north pole, together with the partial derivatives of the point.
+    fn surface_coordinates(&self, n: Vec3) -> ((Float, Float), (Vec3, Vec3)) {
+        let theta = (-n.y).acos();
+        let phi = (-n.z).atan2(n.x) + PI;
+        let sin_theta = theta.sin().max(1e-6);
+        let dpdu = 2.0
+            * PI
+            * self.radius
+            * Vec3 {
+                x: n.z,
+                y: 0.0,
+                z: -n.x,
+            };
+        let dpdv = PI
+            * self.radius
+            * Vec3 {
+                x: -n.y * n.x / sin_theta,
+                y: sin_theta,
+                z: -n.y * n.z / sin_theta,
+            };
+        ((phi / (2.0 * PI), theta / PI), (dpdu, dpdv))
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
         let oc = ray.origin - self.center;
         let a = ray.direction.len() * ray.direction.len();
         let half_b = dot_product(oc, ray.direction);
@@ -64,32 +210,113 @@ impl Hittable for Sphere {
         }
         let squared_discriminant = discriminant.sqrt();
         let mut root = (-half_b - squared_discriminant) / a;
-        if root < t_min || t_max < root {
+        if root <= t_min || t_max < root {
             root = (-half_b + squared_discriminant) / a;
-            if root < t_min || t_max < root {
+            if root <= t_min || t_max < root {
                 return None;
             }
         }
 
-        let n = ray.at(root);
-        let outward_normal = (n - self.center) / self.radius;
-        return Some(HitRecord::new(
-            n,
-            root,
-            &ray,
-            outward_normal,
-            Arc::clone(&self.material),
-        ));
+        // Dividing by the signed radius turns the normals of hollow spheres inward.
+        let outward_normal = (ray.at(root) - self.center) / self.radius;
+        let direction = unit_vector(ray.at(root) - self.center);
+        let (p, p_error) = self.surface_point(direction);
+        let (uv, dpduv) = self.surface_coordinates(direction);
+        return Some(HitRecord {
+            p_error,
+            ..HitRecord::new(
+                p,
+                root,
+                ray,
+                outward_normal,
+                Arc::clone(&self.material),
+                uv,
+                dpduv,
+            )
+        });
+    }
+
+    fn sample_surface(&self) -> Option<(HitRecord, Float)> {
+        let direction = unit_vector(random_in_unit_sphere());
+        let outward_normal = direction * self.radius.abs() / self.radius;
+        let (p, p_error) = self.surface_point(direction);
+        let (uv, dpduv) = self.surface_coordinates(direction);
+        let record = HitRecord {
+            p_error,
+            ..HitRecord::on_surface(p, outward_normal, Arc::clone(&self.material), uv, dpduv)
+        };
+        Some((record, 1.0 / (4.0 * PI * self.radius * self.radius)))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let extent = Vec3 {
+            x: self.radius.abs(),
+            y: self.radius.abs(),
+            z: self.radius.abs(),
+        };
+        Some(Aabb {
+            min: self.center - extent,
+            max: self.center + extent,
+        })
+    }
+
+    /// Culls the lanes that miss the sphere four at a time, then finds the hits of the rest with
+    /// `hit`. The discriminant is computed exactly as there, so no hit is lost.
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: Float,
+        lanes: u32,
+        hits: &mut [Option<HitRecord>],
+    ) -> u32 {
+        let center = Vec3x4::splat(self.center);
+        let radius = Floatx4::splat(self.radius);
+        let zero = Floatx4::splat(0.0);
+        let mut candidates = 0;
+        for group in 0..packet.origins.len() {
+            if (lanes >> (4 * group)) & 0b1111 == 0 {
+                continue;
+            }
+            let oc = packet.origins[group] - center;
+            let direction = packet.directions[group];
+            let a = direction.len() * direction.len();
+            let half_b = oc.dot(direction);
+            let c = oc.dot(oc) - radius * radius;
+            let discriminant = half_b * half_b - a * c;
+            // Negated rather than tested with `le` so NaN lanes stay candidates, as in `hit`.
+            candidates |= (!discriminant.lt(zero).bits() & 0b1111) << (4 * group);
+        }
+        hit_each(self, packet, t_min, t_max, lanes & candidates, hits)
     }
 }
 
-impl Hittable for Box<dyn Hittable + Sync + Send> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+impl Hittable for BoxedHittable {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
         (**self).hit(ray, t_min, t_max)
     }
+
+    fn sample_surface(&self) -> Option<(HitRecord, Float)> {
+        (**self).sample_surface()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        (**self).bounding_box()
+    }
+
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: Float,
+        lanes: u32,
+        hits: &mut [Option<HitRecord>],
+    ) -> u32 {
+        (**self).hit_packet(packet, t_min, t_max, lanes, hits)
+    }
 }
 
-pub fn hit(objects: &[impl Hittable], ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+pub fn hit(objects: &[impl Hittable], ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
     let mut hit_record = HitRecord {
         p: Vec3 {
             x: 0.0,
@@ -104,13 +331,29 @@ pub fn hit(objects: &[impl Hittable], ray: &Ray, t_min: f32, t_max: f32) -> Opti
         t: 0.0,
         front_face: false,
         material: None,
+        object_id: 0,
+        u: 0.0,
+        v: 0.0,
+        dpdu: Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        dpdv: Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        p_error: ZERO,
+        geometric_normal: ZERO,
     };
     let mut hit_anything = false;
     let mut closest_so_far = t_max;
-    for object in objects {
-        if let Some(hit) = object.hit(&ray, t_min, closest_so_far) {
+    for (object_id, object) in objects.iter().enumerate() {
+        if let Some(mut hit) = object.hit(ray, t_min, closest_so_far) {
             hit_anything = true;
             closest_so_far = hit.t;
+            hit.object_id = object_id;
             hit_record = hit;
         }
     }
@@ -120,3 +363,9 @@ pub fn hit(objects: &[impl Hittable], ray: &Ray, t_min: f32, t_max: f32) -> Opti
 
     None
 }
+
+const ZERO: Vec3 = Vec3 {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
@@ -0,0 +1,91 @@
+use crate::{
+    float::Float,
+    ray::Ray,
+    simd::{Floatx4, Vec3x4},
+};
+
+use super::{HitRecord, Hittable};
+
+/// Rays traced together, split into groups of four lanes in structure of arrays layout. The
+/// lanes of the last group past the end of `rays` repeat its last ray and are never reported.
+/// Packets only cull: the `Bvh` skips nodes for all their rays at once and spheres skip the
+/// lanes that miss them, but every hit is found by the scalar `Hittable::hit`. Renders trace
+/// rays one at a time; packets serve the `--benchmark` comparison.
+pub struct RayPacket {
+    pub rays: Vec<Ray>,
+    pub(crate) origins: Vec<Vec3x4>,
+    pub(crate) directions: Vec<Vec3x4>,
+    pub(crate) inverse_directions: Vec<Vec3x4>,
+}
+
+impl RayPacket {
+    /// At most 32 rays, one per bit of a lane mask.
+    pub fn new(rays: Vec<Ray>) -> RayPacket {
+        assert!(!rays.is_empty() && rays.len() <= 32);
+        let groups: Vec<[Ray; 4]> = rays
+            .chunks(4)
+            .map(|chunk| [0, 1, 2, 3].map(|i| chunk[i.min(chunk.len() - 1)]))
+            .collect();
+        let origins = groups
+            .iter()
+            .map(|group| Vec3x4::new(group.map(|ray| ray.origin)))
+            .collect();
+        let directions: Vec<Vec3x4> = groups
+            .iter()
+            .map(|group| Vec3x4::new(group.map(|ray| ray.direction)))
+            .collect();
+        let one = Floatx4::splat(1.0);
+        let inverse_directions = directions
+            .iter()
+            .map(|d| Vec3x4 {
+                x: one / d.x,
+                y: one / d.y,
+                z: one / d.z,
+            })
+            .collect();
+        RayPacket {
+            rays,
+            origins,
+            directions,
+            inverse_directions,
+        }
+    }
+
+    /// Mask with a bit set for every ray.
+    pub fn all(&self) -> u32 {
+        u32::MAX >> (32 - self.rays.len())
+    }
+
+    /// Farthest distance of interest along the rays of group `group`: the closest hit so far or
+    /// `t_max`.
+    pub(crate) fn t_max(&self, group: usize, t_max: Float, hits: &[Option<HitRecord>]) -> Floatx4 {
+        Floatx4::new([0, 1, 2, 3].map(|i| {
+            let lane = (4 * group + i).min(self.rays.len() - 1);
+            hits[lane].as_ref().map_or(t_max, |hit| hit.t)
+        }))
+    }
+}
+
+/// Intersects `object` with the rays of `packet` selected by `lanes` one at a time, replacing
+/// the hits it is closer than. Returns the mask of the lanes whose hit was replaced.
+pub fn hit_each<H: Hittable + ?Sized>(
+    object: &H,
+    packet: &RayPacket,
+    t_min: Float,
+    t_max: Float,
+    lanes: u32,
+    hits: &mut [Option<HitRecord>],
+) -> u32 {
+    let mut updated = 0;
+    for (lane, ray) in packet.rays.iter().enumerate() {
+        if lanes & (1 << lane) == 0 {
+            continue;
+        }
+        let closest = hits[lane].as_ref().map_or(t_max, |hit| hit.t);
+        if let Some(hit_record) = object.hit(ray, t_min, closest) {
+            hits[lane] = Some(hit_record);
+            updated |= 1 << lane;
+        }
+    }
+    updated
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use crate::{
+    float::{gamma, Float},
+    helpers::random_float,
+    material::SharedMaterial,
+    ray::Ray,
+    vec3::{cross_product, dot_product, unit_vector, Point3, Vec3},
+};
+
+use super::{Aabb, HitRecord, Hittable};
+
+/// Parallelogram spanned by the edges `u` and `v` from `corner`, parameterized so that `(u, v)`
+/// runs over `[0, 1]` along the edges.
+pub struct Quad {
+    pub corner: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: SharedMaterial,
+}
+
+impl Quad {
+    /// Point at `(a, b)` on the quad, together with a bound on its rounding error. Points are
+    /// computed from their coordinates rather than taken from along a ray, whose error grows
+    /// with the distance travelled.
+    fn surface_point(&self, a: Float, b: Float) -> (Point3, Vec3) {
+        let p = self.corner + a * self.u + b * self.v;
+        let magnitude = self.corner.abs() + (a * self.u).abs() + (b * self.v).abs();
+        (p, gamma(3) * magnitude)
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let n = cross_product(self.u, self.v);
+        let denominator = dot_product(n, ray.direction);
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+        let t = dot_product(n, self.corner - ray.origin) / denominator;
+        if t <= t_min || t_max < t {
+            return None;
+        }
+        let p = ray.at(t);
+        let offset = p - self.corner;
+        let w = n / n.len_squared();
+        let a = dot_product(w, cross_product(offset, self.v));
+        let b = dot_product(w, cross_product(self.u, offset));
+        if !(0.0..=1.0).contains(&a) || !(0.0..=1.0).contains(&b) {
+            return None;
+        }
+        let (p, p_error) = self.surface_point(a, b);
+        return Some(HitRecord {
+            p_error,
+            ..HitRecord::new(
+                p,
+                t,
+                ray,
+                unit_vector(n),
+                Arc::clone(&self.material),
+                (a, b),
+                (self.u, self.v),
+            )
+        });
+    }
+
+    fn sample_surface(&self) -> Option<(HitRecord, Float)> {
+        let (a, b) = (random_float(), random_float());
+        let n = cross_product(self.u, self.v);
+        let (p, p_error) = self.surface_point(a, b);
+        let record = HitRecord {
+            p_error,
+            ..HitRecord::on_surface(
+                p,
+                unit_vector(n),
+                Arc::clone(&self.material),
+                (a, b),
+                (self.u, self.v),
+            )
+        };
+        Some((record, 1.0 / n.len()))
+    }
+
+    /// Padded along every axis, so the box of a quad lying in an axis plane is not flat.
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bounds = Aabb::around(&[
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ]);
+        let padding = Vec3 {
+            x: 1e-4,
+            y: 1e-4,
+            z: 1e-4,
+        };
+        Some(Aabb {
+            min: bounds.min - padding,
+            max: bounds.max + padding,
+        })
+    }
+}
@@ -0,0 +1,46 @@
+use std::ops::Deref;
+
+use crate::{float::Float, ray::Ray};
+
+use super::{bvh::Bvh, hit, packet::RayPacket, BoxedHittable, HitRecord};
+
+/// Objects of a scene together with the `Bvh` built over them. It dereferences to the objects,
+/// indexed by the `object_id` of the hits found in it.
+pub struct World {
+    objects: Vec<BoxedHittable>,
+    bvh: Bvh,
+}
+
+impl World {
+    pub fn new(objects: Vec<BoxedHittable>) -> World {
+        let bvh = Bvh::new(&objects);
+        World { objects, bvh }
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        self.bvh.hit(&self.objects, ray, t_min, t_max)
+    }
+
+    /// Same as `hit`, testing every object in turn instead of traversing the `Bvh`.
+    pub fn hit_linear(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        hit(&self.objects, ray, t_min, t_max)
+    }
+
+    /// Closest hits of all rays of `packet`, in the order of its rays.
+    pub fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: Float,
+    ) -> Vec<Option<HitRecord>> {
+        self.bvh.hit_packet(&self.objects, packet, t_min, t_max)
+    }
+}
+
+impl Deref for World {
+    type Target = [BoxedHittable];
+
+    fn deref(&self) -> &Self::Target {
+        &self.objects
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{float::Float, helpers::clamp, vec3::Color};
+
+/// Grid of colors stored row by row from the top, each row from the left. Pixels are addressed
+/// by column `x` and row `y`.
+#[derive(Clone)]
+pub struct Image {
+    pub width: i32,
+    pub height: i32,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    /// Black image.
+    pub fn new(width: i32, height: i32) -> Image {
+        Image::from_pixels(width, height, vec![BLACK; (width * height) as usize])
+    }
+
+    /// Image of `pixels` in row-major order from the top left.
+    pub fn from_pixels(width: i32, height: i32, pixels: Vec<Color>) -> Image {
+        assert_eq!(pixels.len(), (width * height) as usize);
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Decodes red, green and blue bytes stored with the sRGB transfer curve.
+    pub fn from_srgb8(width: i32, height: i32, bytes: &[u8]) -> Image {
+        let pixels = bytes
+            .chunks_exact(3)
+            .map(|c| Color {
+                x: srgb_to_linear(c[0] as Float / 255.0),
+                y: srgb_to_linear(c[1] as Float / 255.0),
+                z: srgb_to_linear(c[2] as Float / 255.0),
+            })
+            .collect();
+        Image::from_pixels(width, height, pixels)
+    }
+
+    /// Image of red, green and blue values taken as they are.
+    pub fn from_linear_f32(width: i32, height: i32, values: &[f32]) -> Image {
+        let pixels = values
+            .chunks_exact(3)
+            .map(|c| Color {
+                x: c[0] as Float,
+                y: c[1] as Float,
+                z: c[2] as Float,
+            })
+            .collect();
+        Image::from_pixels(width, height, pixels)
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, color: Color) {
+        let index = self.index(x, y);
+        self.pixels[index] = color;
+    }
+
+    /// Adds `color` to the pixel, as samples and splats landing on it are.
+    pub fn accumulate(&mut self, x: i32, y: i32, color: Color) {
+        let index = self.index(x, y);
+        self.pixels[index] += &color;
+    }
+
+    /// Image with `f` applied to every pixel.
+    pub fn map(&self, f: impl Fn(Color) -> Color) -> Image {
+        Image::from_pixels(
+            self.width,
+            self.height,
+            self.pixels.iter().map(|c| f(*c)).collect(),
+        )
+    }
+
+    /// The `width` by `height` part of the image whose top left pixel is at `x`, `y`.
+    pub fn crop(&self, x: i32, y: i32, width: i32, height: i32) -> Image {
+        assert!(x >= 0 && y >= 0 && x + width <= self.width && y + height <= self.height);
+        let pixels = (y..y + height)
+            .flat_map(|row| (x..x + width).map(move |column| (column, row)))
+            .map(|(column, row)| self.get(column, row))
+            .collect();
+        Image::from_pixels(width, height, pixels)
+    }
+
+    /// Copies `image` over this one with its top left pixel at `x`, `y`.
+    pub fn paste(&mut self, x: i32, y: i32, image: &Image) {
+        assert!(
+            x >= 0 && y >= 0 && x + image.width <= self.width && y + image.height <= self.height
+        );
+        for row in 0..image.height {
+            for column in 0..image.width {
+                self.set(x + column, y + row, image.get(column, row));
+            }
+        }
+    }
+
+    /// Image scaled to `width` by `height`, one axis after the other. Along an axis that shrinks
+    /// each new pixel averages the pixels it covers, along one that grows it is interpolated
+    /// linearly between the centers of the two nearest.
+    pub fn resize(&self, width: i32, height: i32) -> Image {
+        let columns = resampling(self.width, width);
+        let rows = resampling(self.height, height);
+        let mut wide = Image::new(width, self.height);
+        for y in 0..self.height {
+            for (x, taps) in columns.iter().enumerate() {
+                for (column, weight) in taps {
+                    wide.accumulate(x as i32, y, *weight * self.get(*column, y));
+                }
+            }
+        }
+        let mut resized = Image::new(width, height);
+        for (y, taps) in rows.iter().enumerate() {
+            for x in 0..width {
+                for (row, weight) in taps {
+                    resized.accumulate(x, y as i32, *weight * wide.get(x, *row));
+                }
+            }
+        }
+        resized
+    }
+
+    /// Turns the image upside down.
+    pub fn flip_vertical(&mut self) {
+        let width = self.width as usize;
+        for y in 0..(self.height / 2) as usize {
+            let mirrored = self.height as usize - y - 1;
+            let (head, tail) = self.pixels.split_at_mut(mirrored * width);
+            head[y * width..(y + 1) * width].swap_with_slice(&mut tail[..width]);
+        }
+    }
+
+    /// Mirrors the image left to right.
+    pub fn flip_horizontal(&mut self) {
+        for row in self.pixels.chunks_exact_mut(self.width as usize) {
+            row.reverse();
+        }
+    }
+
+    /// Red, green and blue bytes of every pixel encoded with the sRGB transfer curve, for
+    /// display. Colors outside `[0, 1]` are clipped.
+    pub fn to_srgb8(&self) -> Vec<u8> {
+        self.bytes(linear_to_srgb)
+    }
+
+    /// Red, green and blue bytes proportional to the colors, for passes holding data rather
+    /// than light. Colors outside `[0, 1]` are clipped.
+    pub fn to_linear8(&self) -> Vec<u8> {
+        self.bytes(|c| c)
+    }
+
+    /// Red, green and blue values of every pixel, unclipped.
+    // `Float` is already `f32` unless built with the `f64` feature.
+    #[allow(clippy::unnecessary_cast)]
+    pub fn to_linear_f32(&self) -> Vec<f32> {
+        self.pixels
+            .iter()
+            .flat_map(|c| [c.x as f32, c.y as f32, c.z as f32])
+            .collect()
+    }
+
+    fn bytes(&self, encode: impl Fn(Float) -> Float) -> Vec<u8> {
+        let byte = |c: Float| (255.0 * encode(clamp(c, 0.0, 1.0)) + 0.5) as u8;
+        self.pixels
+            .iter()
+            .flat_map(|c| [byte(c.x), byte(c.y), byte(c.z)])
+            .collect()
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        debug_assert!(x >= 0 && x < self.width && y >= 0 && y < self.height);
+        (y * self.width + x) as usize
+    }
+}
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+pub fn linear_to_srgb(c: Float) -> Float {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn srgb_to_linear(c: Float) -> Float {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Pixels of an axis `from` pixels long that each pixel of the same axis resized to `to` pixels
+/// is made of, with their weights.
+fn resampling(from: i32, to: i32) -> Vec<Vec<(i32, Float)>> {
+    let scale = from as Float / to as Float;
+    (0..to)
+        .map(|i| {
+            if scale > 1.0 {
+                let (start, end) = (i as Float * scale, (i + 1) as Float * scale);
+                (start as i32..(end.ceil() as i32).min(from))
+                    .map(|j| {
+                        let covered = end.min((j + 1) as Float) - start.max(j as Float);
+                        (j, covered / scale)
+                    })
+                    .collect()
+            } else {
+                let position = ((i as Float + 0.5) * scale - 0.5).max(0.0);
+                let before = (position as i32).min(from - 1);
+                let t = position - before as Float;
+                vec![(before, 1.0 - t), ((before + 1).min(from - 1), t)]
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+use crate::{
+    float::{consts::PI, Float},
+    helpers::{random_float, random_in_unit_sphere},
+    hittable::{HitRecord, World},
+    vec3::{dot_product, unit_vector, Vec3},
+};
+
+/// Emissive object of the world that light paths can start from.
+struct AreaLight {
+    object_id: usize,
+    /// Density of the points picked by `Hittable::sample_surface`, per unit area.
+    pdf_area: Float,
+}
+
+/// Start of a path leaving a light.
+pub struct EmissionSample {
+    /// Point on the light, facing the direction of emission.
+    pub record: HitRecord,
+    /// Density of the point per unit area, including the choice of the light.
+    pub pdf_position: Float,
+    pub direction: Vec3,
+    pub pdf_direction: Float,
+}
+
+/// Emissive objects of a world that can be sampled, each picked with equal probability.
+pub struct AreaLights {
+    lights: Vec<AreaLight>,
+}
+
+impl AreaLights {
+    pub fn new(world: &World) -> AreaLights {
+        let lights = world
+            .iter()
+            .enumerate()
+            .filter_map(|(object_id, object)| {
+                let (record, pdf_area) = object.sample_surface()?;
+                let emitted = record.material.as_ref()?.emitted(&record);
+                if emitted.x.max(emitted.y).max(emitted.z) > 0.0 {
+                    Some(AreaLight {
+                        object_id,
+                        pdf_area,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect();
+        AreaLights { lights }
+    }
+
+    /// Area density with which `sample_emission` picks the point hit by `hit_record`, zero for
+    /// emitters that are not sampled.
+    pub fn origin_pdf(&self, hit_record: &HitRecord) -> Float {
+        self.lights
+            .iter()
+            .find(|light| light.object_id == hit_record.object_id)
+            .map_or(0.0, |light| light.pdf_area / self.lights.len() as Float)
+    }
+
+    /// Picks a light, a point on it and a cosine weighted direction of emission.
+    pub fn sample_emission(&self, world: &World) -> Option<EmissionSample> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let count = self.lights.len();
+        let light = &self.lights[((random_float() * count as Float) as usize).min(count - 1)];
+        let (record, pdf_area) = world[light.object_id].sample_surface()?;
+        let normal = record.normal;
+        let mut direction = normal + unit_vector(random_in_unit_sphere());
+        if direction.near_zero() {
+            direction = normal;
+        }
+        let direction = unit_vector(direction);
+        Some(EmissionSample {
+            record,
+            pdf_position: pdf_area / count as Float,
+            direction,
+            pdf_direction: emission_pdf(normal, direction),
+        })
+    }
+}
+
+/// Solid angle density of cosine weighted emission around `normal`.
+pub fn emission_pdf(normal: Vec3, direction: Vec3) -> Float {
+    dot_product(normal, unit_vector(direction)).max(0.0) / PI
+}
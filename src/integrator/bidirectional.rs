@@ -0,0 +1,510 @@
+use crate::{
+    camera::CameraModel,
+    float::Float,
+    hittable::{HitRecord, World},
+    light::Light,
+    ray::{offset_origin, Ray},
+    spectral::upsample,
+    vec3::{dot_product, unit_vector, Color, Point3, Vec3},
+};
+
+use super::{
+    area_lights::{emission_pdf, AreaLights, EmissionSample},
+    direct_light, light_path_scale, Background, Bounces, DepthLimits, Integrator, Splat,
+};
+
+enum VertexKind {
+    Camera,
+    Light,
+    Surface(HitRecord),
+}
+
+/// Vertex of a camera or light subpath. Densities are per unit area at the vertex, `pdf_fwd`
+/// for generating it from the previous vertex of its own subpath and `pdf_rev` for generating
+/// it the other way round.
+struct Vertex {
+    kind: VertexKind,
+    p: Point3,
+    /// Surface normal on the side the vertex was reached from, or the viewing direction of the
+    /// camera.
+    normal: Vec3,
+    /// Unit vector towards the previous vertex of the subpath.
+    wo: Vec3,
+    /// Throughput of the subpath up to and including this vertex, divided by its density.
+    beta: Color,
+    pdf_fwd: Float,
+    pdf_rev: Float,
+    /// Set for vertices that cannot be connected to, such as those on specular materials.
+    delta: bool,
+    /// Bound on the rounding error of `p`, see `HitRecord::p_error`.
+    p_error: Vec3,
+}
+
+impl Vertex {
+    /// Origin for a connection from the vertex towards `target`, off the surface it lies on.
+    fn spawn_origin(&self, target: Point3) -> Point3 {
+        match &self.kind {
+            VertexKind::Surface(hit_record) => hit_record.spawn_origin(target - self.p),
+            _ => offset_origin(self.p, self.p_error, self.normal, target - self.p),
+        }
+    }
+
+    /// BSDF, or emission profile for lights, times the cosine towards `target`.
+    fn scattering(&self, target: Point3, wavelength: Option<Float>) -> Color {
+        let wi = unit_vector(target - self.p);
+        match &self.kind {
+            VertexKind::Surface(hit_record) => {
+                let material = hit_record.material.as_ref().unwrap();
+                match material.eval(hit_record, self.wo, wi) {
+                    Some(f) => upsample(f, wavelength),
+                    None => BLACK,
+                }
+            }
+            VertexKind::Light => dot_product(self.normal, wi).max(0.0) * WHITE,
+            VertexKind::Camera => BLACK,
+        }
+    }
+
+    /// Density with which a subpath arriving from `prev` continues to `next`.
+    fn pdf(&self, camera: &dyn CameraModel, prev: Option<&Vertex>, next: &Vertex) -> Float {
+        let to_next = next.p - self.p;
+        let pdf_direction = match &self.kind {
+            VertexKind::Camera => camera.pdf_direction(to_next),
+            VertexKind::Light => emission_pdf(self.normal, to_next),
+            VertexKind::Surface(hit_record) => match prev {
+                Some(prev) => hit_record.material.as_ref().unwrap().pdf(
+                    hit_record,
+                    unit_vector(prev.p - self.p),
+                    unit_vector(to_next),
+                ),
+                None => 0.0,
+            },
+        };
+        to_area(pdf_direction, self.p, next)
+    }
+}
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+const WHITE: Color = Color {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+};
+
+const ZERO: Vec3 = Vec3 {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+/// Converts a solid angle density at `from` into an area density at `to`.
+fn to_area(pdf_direction: Float, from: Point3, to: &Vertex) -> Float {
+    let direction = to.p - from;
+    let distance_squared = direction.len_squared();
+    match to.kind {
+        VertexKind::Camera => pdf_direction / distance_squared,
+        _ => {
+            let cos_theta = dot_product(to.normal, direction).abs() / distance_squared.sqrt();
+            pdf_direction * cos_theta / distance_squared
+        }
+    }
+}
+
+fn remap0(pdf: Float) -> Float {
+    if pdf != 0.0 {
+        pdf
+    } else {
+        1.0
+    }
+}
+
+/// Bidirectional path tracer. Every camera sample traces a subpath from the camera and one from
+/// a randomly chosen area light, then joins every pair of their vertices, weighting each way of
+/// building a path with the balance heuristic. Paths connected to the camera directly are
+/// returned as splats, which needs a camera model that supports `CameraModel::connect`.
+///
+/// Materials without `Material::eval` are treated as specular: paths go through them but are
+/// never joined at them, so glossy materials are only reached by the strategies that sample
+/// through them.
+///
+/// `lights` cannot be hit nor sampled as the start of a light subpath, so the only strategy
+/// that finds their light is a shadow ray from each camera vertex, weighted fully.
+pub struct Bidirectional {
+    pub lights: Vec<Light>,
+    pub depths: DepthLimits,
+    pub background: Background,
+    area_lights: AreaLights,
+}
+
+impl Bidirectional {
+    /// Collects the emissive objects of `world` that can be sampled as area lights.
+    pub fn new(
+        world: &World,
+        lights: Vec<Light>,
+        depths: DepthLimits,
+        background: Background,
+    ) -> Bidirectional {
+        Bidirectional {
+            lights,
+            depths,
+            background,
+            area_lights: AreaLights::new(world),
+        }
+    }
+
+    /// Extends `path` by following `ray` through the scene until it leaves, is absorbed or
+    /// reaches `max_vertices`. Returns the throughput times the background radiance when the
+    /// ray escapes.
+    fn random_walk(
+        &self,
+        world: &World,
+        ray: Ray,
+        beta: Color,
+        pdf_direction: Float,
+        max_vertices: usize,
+        path: &mut Vec<Vertex>,
+    ) -> Color {
+        let from_light = matches!(path[0].kind, VertexKind::Light);
+        let mut ray = ray;
+        let mut beta = beta;
+        let mut pdf_direction = pdf_direction;
+        let mut bounces = Bounces::default();
+        while path.len() < max_vertices {
+            let hit_record = match world.hit(&ray, 0.0, Float::MAX) {
+                Some(hit_record) => hit_record,
+                None => return beta * upsample(self.background.radiance(&ray), ray.wavelength),
+            };
+            let material = hit_record.material.clone().unwrap();
+            let scatter = material.scatter(&ray, &hit_record);
+            let mut vertex = Vertex {
+                p: hit_record.p,
+                normal: hit_record.normal,
+                wo: -unit_vector(ray.direction),
+                beta,
+                pdf_fwd: 0.0,
+                pdf_rev: 0.0,
+                delta: false,
+                p_error: hit_record.p_error,
+                kind: VertexKind::Surface(hit_record),
+            };
+            vertex.pdf_fwd = to_area(pdf_direction, path.last().unwrap().p, &vertex);
+            let (attenuation, scattered, lobe) = match scatter {
+                Some(scatter) => scatter,
+                None => {
+                    path.push(vertex);
+                    break;
+                }
+            };
+            let wi = unit_vector(scattered.direction);
+            let pdf_reverse = match &vertex.kind {
+                VertexKind::Surface(hit_record)
+                    if material.eval(hit_record, vertex.wo, wi).is_some() =>
+                {
+                    pdf_direction = material.pdf(hit_record, vertex.wo, wi);
+                    material.pdf(hit_record, wi, vertex.wo)
+                }
+                _ => {
+                    vertex.delta = true;
+                    pdf_direction = 0.0;
+                    0.0
+                }
+            };
+            let prev = path.last_mut().unwrap();
+            prev.pdf_rev = to_area(pdf_reverse, vertex.p, prev);
+            path.push(vertex);
+
+            bounces = bounces.after(lobe);
+            if bounces.exceeds(&self.depths) {
+                break;
+            }
+            beta = beta * upsample(attenuation, ray.wavelength);
+            if from_light {
+                beta *= light_path_scale(lobe);
+            }
+            ray = scattered;
+        }
+        BLACK
+    }
+
+    /// Starts a subpath on a random light, at the time and wavelength of `ray`.
+    fn light_subpath(&self, world: &World, ray: &Ray, path: &mut Vec<Vertex>) {
+        let EmissionSample {
+            record,
+            pdf_position,
+            direction,
+            pdf_direction,
+        } = match self.area_lights.sample_emission(world) {
+            Some(sample) => sample,
+            None => return,
+        };
+        let emitted = record.material.as_ref().unwrap().emitted(&record);
+        let normal = record.normal;
+        let beta = upsample(emitted, ray.wavelength) / pdf_position;
+        path.push(Vertex {
+            kind: VertexKind::Light,
+            p: record.p,
+            normal,
+            wo: normal,
+            beta,
+            pdf_fwd: pdf_position,
+            pdf_rev: 0.0,
+            delta: false,
+            p_error: record.p_error,
+        });
+        let emitted_ray = record.spawn_ray(ray, direction);
+        let beta = (dot_product(normal, direction) / pdf_direction) * beta;
+        let max_vertices = self.depths.total as usize + 1;
+        self.random_walk(world, emitted_ray, beta, pdf_direction, max_vertices, path);
+    }
+
+    /// Whether the segment between the vertices `a` and `b` is unobstructed. Both ends are moved
+    /// off their surfaces, so the whole segment is tested short of its end point, which stays on
+    /// the surface of `b` when that point has no rounding error to step over.
+    fn visible(world: &World, ray: &Ray, a: &Vertex, b: &Vertex) -> bool {
+        let origin = a.spawn_origin(b.p);
+        let shadow_ray = Ray {
+            origin,
+            direction: b.spawn_origin(a.p) - origin,
+            time: ray.time,
+            wavelength: ray.wavelength,
+        };
+        world.hit(&shadow_ray, 0.0, Float::next_down(1.0)).is_none()
+    }
+
+    /// Balance heuristic weight of the path made of the first `t` camera and `s` light vertices,
+    /// with `sampled` standing in for the camera vertex when `t` is one.
+    fn mis_weight(
+        &self,
+        camera: &dyn CameraModel,
+        camera_path: &[Vertex],
+        light_path: &[Vertex],
+        (s, t): (usize, usize),
+        sampled: Option<&Vertex>,
+    ) -> Float {
+        if s + t == 2 {
+            return 1.0;
+        }
+        let pt = match sampled {
+            Some(vertex) => vertex,
+            None => &camera_path[t - 1],
+        };
+        let pt_minus = if t > 1 {
+            Some(&camera_path[t - 2])
+        } else {
+            None
+        };
+        let qs = if s > 0 {
+            Some(&light_path[s - 1])
+        } else {
+            None
+        };
+        let qs_minus = if s > 1 {
+            Some(&light_path[s - 2])
+        } else {
+            None
+        };
+
+        // (pdf_fwd, pdf_rev, delta) of every vertex, with the densities around the connection
+        // replaced by those of the path as joined.
+        let mut camera_pdfs: Vec<(Float, Float, bool)> = camera_path[..t]
+            .iter()
+            .map(|v| (v.pdf_fwd, v.pdf_rev, v.delta))
+            .collect();
+        let mut light_pdfs: Vec<(Float, Float, bool)> = light_path[..s]
+            .iter()
+            .map(|v| (v.pdf_fwd, v.pdf_rev, v.delta))
+            .collect();
+        camera_pdfs[t - 1] = (pt.pdf_fwd, 0.0, false);
+        camera_pdfs[t - 1].1 = match qs {
+            Some(qs) => qs.pdf(camera, qs_minus, pt),
+            None => match &pt.kind {
+                VertexKind::Surface(hit_record) => self.area_lights.origin_pdf(hit_record),
+                _ => 0.0,
+            },
+        };
+        if let Some(pt_minus) = pt_minus {
+            camera_pdfs[t - 2].1 = match qs {
+                Some(qs) => pt.pdf(camera, Some(qs), pt_minus),
+                None => to_area(emission_pdf(pt.normal, pt_minus.p - pt.p), pt.p, pt_minus),
+            };
+        }
+        if let Some(qs) = qs {
+            light_pdfs[s - 1].1 = pt.pdf(camera, pt_minus, qs);
+            light_pdfs[s - 1].2 = false;
+        }
+        if let Some(qs_minus) = qs_minus {
+            light_pdfs[s - 2].1 = qs.unwrap().pdf(camera, Some(pt), qs_minus);
+        }
+
+        let mut sum = 0.0;
+        let mut ratio = 1.0;
+        for i in (1..t).rev() {
+            ratio *= remap0(camera_pdfs[i].1) / remap0(camera_pdfs[i].0);
+            if !camera_pdfs[i].2 && !camera_pdfs[i - 1].2 {
+                sum += ratio;
+            }
+        }
+        ratio = 1.0;
+        for i in (0..s).rev() {
+            ratio *= remap0(light_pdfs[i].1) / remap0(light_pdfs[i].0);
+            let previous_delta = i > 0 && light_pdfs[i - 1].2;
+            if !light_pdfs[i].2 && !previous_delta {
+                sum += ratio;
+            }
+        }
+        1.0 / (1.0 + sum)
+    }
+
+    /// Radiance of the path joining the first `t >= 2` camera and `s` light vertices.
+    fn connect(
+        &self,
+        world: &World,
+        camera: &dyn CameraModel,
+        ray: &Ray,
+        camera_path: &[Vertex],
+        light_path: &[Vertex],
+        (s, t): (usize, usize),
+    ) -> Color {
+        let pt = &camera_path[t - 1];
+        if s == 0 {
+            let hit_record = match &pt.kind {
+                VertexKind::Surface(hit_record) => hit_record,
+                _ => return BLACK,
+            };
+            let emitted = hit_record.material.as_ref().unwrap().emitted(hit_record);
+            if emitted.x.max(emitted.y).max(emitted.z) <= 0.0 {
+                return BLACK;
+            }
+            let weight = if self.area_lights.origin_pdf(hit_record) > 0.0 {
+                self.mis_weight(camera, camera_path, light_path, (s, t), None)
+            } else {
+                1.0
+            };
+            return weight * (pt.beta * upsample(emitted, ray.wavelength));
+        }
+        let qs = &light_path[s - 1];
+        if pt.delta || qs.delta {
+            return BLACK;
+        }
+        let distance_squared = (qs.p - pt.p).len_squared();
+        let contribution = (qs.beta * qs.scattering(pt.p, ray.wavelength))
+            * (pt.beta * pt.scattering(qs.p, ray.wavelength))
+            / distance_squared;
+        if contribution.x.max(contribution.y).max(contribution.z) <= 0.0
+            || !Bidirectional::visible(world, ray, pt, qs)
+        {
+            return BLACK;
+        }
+        self.mis_weight(camera, camera_path, light_path, (s, t), None) * contribution
+    }
+
+    /// Joins the first `s` light vertices directly to the lens.
+    fn connect_to_camera(
+        &self,
+        world: &World,
+        camera: &dyn CameraModel,
+        ray: &Ray,
+        camera_path: &[Vertex],
+        light_path: &[Vertex],
+        s: usize,
+    ) -> Option<Splat> {
+        let qs = &light_path[s - 1];
+        if qs.delta {
+            return None;
+        }
+        let connection = camera.connect(qs.p)?;
+        let to_point = qs.p - connection.lens_point;
+        let cos_theta = dot_product(connection.normal, unit_vector(to_point));
+        let contribution = (connection.importance * cos_theta / to_point.len_squared())
+            * (qs.beta * qs.scattering(connection.lens_point, ray.wavelength));
+        let sampled = Vertex {
+            kind: VertexKind::Camera,
+            p: connection.lens_point,
+            normal: connection.normal,
+            wo: connection.normal,
+            beta: WHITE,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+            delta: false,
+            p_error: ZERO,
+        };
+        if contribution.x.max(contribution.y).max(contribution.z) <= 0.0
+            || !Bidirectional::visible(world, ray, qs, &sampled)
+        {
+            return None;
+        }
+        let weight = self.mis_weight(camera, camera_path, light_path, (s, 1), Some(&sampled));
+        Some(Splat {
+            u: connection.u,
+            v: connection.v,
+            color: weight * contribution,
+        })
+    }
+}
+
+impl Integrator for Bidirectional {
+    fn radiance(
+        &self,
+        ray: &Ray,
+        world: &World,
+        camera: &dyn CameraModel,
+        splats: &mut Vec<Splat>,
+    ) -> Color {
+        let max_depth = self.depths.total as usize;
+        // Cameras that cannot be connected to only generate rays, like a specular vertex.
+        let pdf_direction = camera.pdf_direction(ray.direction);
+        let mut camera_path = vec![Vertex {
+            kind: VertexKind::Camera,
+            p: ray.origin,
+            normal: unit_vector(ray.direction),
+            wo: unit_vector(ray.direction),
+            beta: WHITE,
+            pdf_fwd: 1.0,
+            pdf_rev: 0.0,
+            delta: pdf_direction == 0.0,
+            p_error: ZERO,
+        }];
+        // Light from the background is only found by camera subpaths leaving the scene.
+        let mut radiance = self.random_walk(
+            world,
+            *ray,
+            WHITE,
+            pdf_direction,
+            max_depth + 2,
+            &mut camera_path,
+        );
+        let mut light_path = vec![];
+        self.light_subpath(world, ray, &mut light_path);
+
+        for t in 1..=camera_path.len() {
+            let pt = &camera_path[t - 1];
+            if let VertexKind::Surface(hit_record) = &pt.kind {
+                if t - 1 <= max_depth {
+                    let direct = direct_light(&self.lights, world, ray, hit_record, pt.wo);
+                    radiance += &(pt.beta * direct);
+                }
+            }
+            for s in 0..=light_path.len() {
+                if (s == 1 && t == 1) || s + t < 2 || s + t - 2 > max_depth {
+                    continue;
+                }
+                if t == 1 {
+                    if let Some(splat) =
+                        self.connect_to_camera(world, camera, ray, &camera_path, &light_path, s)
+                    {
+                        splats.push(splat);
+                    }
+                } else {
+                    radiance +=
+                        &self.connect(world, camera, ray, &camera_path, &light_path, (s, t));
+                }
+            }
+        }
+        radiance
+    }
+}
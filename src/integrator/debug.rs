@@ -0,0 +1,178 @@
+use crate::{
+    camera::CameraModel,
+    float::Float,
+    helpers::{random_float, random_in_unit_sphere},
+    hittable::{HitRecord, World},
+    ray::Ray,
+    spectral::upsample,
+    vec3::{unit_vector, Color},
+};
+
+use super::{Bounces, DepthLimits, Integrator, Splat};
+
+/// Quantity a `DebugIntegrator` shows.
+#[derive(Copy, Clone)]
+pub enum DebugView {
+    /// Geometric normal facing the camera, mapped from `[-1, 1]` to `[0, 1]`.
+    Normal,
+    /// Distance to the first hit, white at the camera fading to black at `DebugSettings::far`.
+    Depth,
+    Albedo,
+    /// Fraction of cosine weighted rays leaving the first hit that travel
+    /// `DebugSettings::ao_distance` without hitting anything.
+    AmbientOcclusion,
+    /// Surface parameterization as red and green, with the edges of the `(u, v)` patch drawn in
+    /// black: the outline of quads and the seam and poles of spheres.
+    Wireframe,
+    /// Number of scatter events of a path traced sample, from blue for none to red for
+    /// `DebugSettings::bounce_range` or more.
+    Bounces,
+}
+
+#[derive(Copy, Clone)]
+pub struct DebugSettings {
+    pub ao_rays: u32,
+    pub ao_distance: Float,
+    pub far: Float,
+    pub bounce_range: i32,
+}
+
+impl Default for DebugSettings {
+    fn default() -> DebugSettings {
+        DebugSettings {
+            ao_rays: 16,
+            ao_distance: 1.0,
+            far: 30.0,
+            bounce_range: 8,
+        }
+    }
+}
+
+/// Fast preview integrator shading camera rays by properties of what they hit instead of the
+/// light arriving along them. Rays that escape the scene are black.
+pub struct DebugIntegrator {
+    pub view: DebugView,
+    pub settings: DebugSettings,
+    pub depths: DepthLimits,
+}
+
+/// Width of wireframe edges relative to the distance to the camera.
+const EDGE_WIDTH: Float = 0.002;
+
+impl DebugIntegrator {
+    fn ambient_occlusion(&self, world: &World, ray: &Ray, hit_record: &HitRecord) -> Float {
+        let normal = hit_record.normal;
+        let rays = self.settings.ao_rays.max(1);
+        let unoccluded = (0..rays)
+            .filter(|_| {
+                let mut direction = normal + unit_vector(random_in_unit_sphere());
+                if direction.near_zero() {
+                    direction = normal;
+                }
+                let occlusion_ray = hit_record.spawn_ray(ray, unit_vector(direction));
+                world
+                    .hit(&occlusion_ray, 0.0, self.settings.ao_distance)
+                    .is_none()
+            })
+            .count();
+        unoccluded as Float / rays as Float
+    }
+
+    /// Scatter events of a path started along `ray`, up to `DepthLimits::total`.
+    fn bounces(&self, world: &World, ray: &Ray) -> i32 {
+        let mut ray = *ray;
+        let mut throughput = Color {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        let mut bounces = Bounces::default();
+        while bounces.total < self.depths.total {
+            let hit_record = match world.hit(&ray, 0.0, Float::MAX) {
+                Some(hit_record) => hit_record,
+                None => break,
+            };
+            let material = hit_record.material.as_ref().unwrap();
+            let (attenuation, scattered, lobe) = match material.scatter(&ray, &hit_record) {
+                Some(scatter) => scatter,
+                None => break,
+            };
+            let next = bounces.after(lobe);
+            if next.exceeds(&self.depths) {
+                break;
+            }
+            bounces = next;
+            throughput = throughput * attenuation;
+            let survival = self
+                .depths
+                .survival_probability(bounces, throughput.x.max(throughput.y).max(throughput.z));
+            if random_float() >= survival {
+                break;
+            }
+            throughput /= survival;
+            ray = scattered;
+        }
+        bounces.total
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn radiance(
+        &self,
+        ray: &Ray,
+        world: &World,
+        _camera: &dyn CameraModel,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        let hit_record = match world.hit(ray, 0.0, Float::MAX) {
+            Some(hit_record) => hit_record,
+            None => return BLACK,
+        };
+        let distance = hit_record.t * ray.direction.len();
+        let color = match self.view {
+            DebugView::Normal => 0.5 * (hit_record.normal + WHITE),
+            DebugView::Depth => (1.0 - distance / self.settings.far).max(0.0) * WHITE,
+            DebugView::Albedo => {
+                let material = hit_record.material.as_ref().unwrap();
+                material.albedo(&hit_record)
+            }
+            DebugView::AmbientOcclusion => self.ambient_occlusion(world, ray, &hit_record) * WHITE,
+            DebugView::Wireframe => {
+                let (u, v) = (hit_record.u, hit_record.v);
+                let to_edge = (u.min(1.0 - u) * hit_record.dpdu.len())
+                    .min(v.min(1.0 - v) * hit_record.dpdv.len());
+                if to_edge < EDGE_WIDTH * distance {
+                    BLACK
+                } else {
+                    Color { x: u, y: v, z: 0.2 }
+                }
+            }
+            DebugView::Bounces => {
+                heatmap(self.bounces(world, ray) as Float / self.settings.bounce_range as Float)
+            }
+        };
+        upsample(color, ray.wavelength)
+    }
+}
+
+/// False color for `t` in `[0, 1]`, running from blue through green to red.
+fn heatmap(t: Float) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    Color {
+        x: (2.0 * t - 1.0).clamp(0.0, 1.0),
+        y: 1.0 - (2.0 * t - 1.0).abs(),
+        z: (1.0 - 2.0 * t).clamp(0.0, 1.0),
+    }
+}
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+const WHITE: Color = Color {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+};
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use crate::{
+    camera::CameraModel,
+    float::Float,
+    hittable::{HitRecord, World},
+    light::Light,
+    material::Lobe,
+    ray::Ray,
+    spectral::upsample,
+    vec3::{unit_vector, Color, Vec3},
+};
+
+pub use bidirectional::Bidirectional;
+pub use debug::{DebugIntegrator, DebugSettings, DebugView};
+pub use path::PathTracer;
+pub use photon::{BoundingSphere, PhotonMapper, PhotonSettings};
+pub use whitted::Whitted;
+
+mod area_lights;
+mod bidirectional;
+mod debug;
+mod path;
+mod photon;
+mod photon_map;
+mod whitted;
+
+/// Integrator shared by the threads rendering a frame.
+pub type SharedIntegrator = Arc<dyn Integrator + Send + Sync>;
+
+/// Light transport algorithm estimating the radiance arriving along camera rays.
+pub trait Integrator {
+    /// Radiance arriving along `ray`, cast by `camera`. For rays carrying a wavelength this is
+    /// the spectral radiance at that wavelength, replicated in all three channels. Integrators
+    /// that also trace paths from the lights push what they find for other parts of the film to
+    /// `splats`.
+    fn radiance(
+        &self,
+        ray: &Ray,
+        world: &World,
+        camera: &dyn CameraModel,
+        splats: &mut Vec<Splat>,
+    ) -> Color;
+}
+
+/// Contribution of a light path to the film position `(u, v)`, in the units of a camera sample.
+pub struct Splat {
+    pub u: Float,
+    pub v: Float,
+    pub color: Color,
+}
+
+/// Radiance arriving from outside the scene.
+#[derive(Copy, Clone)]
+pub enum Background {
+    /// Blue to white gradient over the vertical direction.
+    Sky,
+    Uniform(Color),
+}
+
+impl Background {
+    pub fn radiance(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Sky => {
+                let unit_direction = unit_vector(ray.direction);
+                let t = 0.5 * (unit_direction.y + 1.0);
+                (1.0 - t)
+                    * Color {
+                        x: 1.0,
+                        y: 1.0,
+                        z: 1.0,
+                    }
+                    + t * Color {
+                        x: 0.5,
+                        y: 0.7,
+                        z: 1.0,
+                    }
+            }
+            Background::Uniform(color) => *color,
+        }
+    }
+}
+
+/// Maximum number of scatter events along a path, overall and for each kind of lobe.
+#[derive(Copy, Clone)]
+pub struct DepthLimits {
+    pub total: i32,
+    pub diffuse: i32,
+    pub specular: i32,
+    pub transmission: i32,
+    pub volume: i32,
+    /// Number of bounces after which paths are randomly terminated with a probability based on
+    /// their throughput, compensating the surviving ones (Russian roulette).
+    pub roulette_after: i32,
+}
+
+impl Default for DepthLimits {
+    fn default() -> DepthLimits {
+        DepthLimits {
+            total: 50,
+            diffuse: 50,
+            specular: 50,
+            transmission: 50,
+            volume: 50,
+            roulette_after: 3,
+        }
+    }
+}
+
+impl DepthLimits {
+    /// Probability of continuing a path after `bounces` given the largest component of its
+    /// throughput.
+    pub fn survival_probability(&self, bounces: Bounces, throughput: Float) -> Float {
+        if bounces.total < self.roulette_after {
+            return 1.0;
+        }
+        throughput.min(1.0)
+    }
+}
+
+/// Scatter events made so far along a path.
+#[derive(Copy, Clone, Default)]
+pub struct Bounces {
+    pub total: i32,
+    pub diffuse: i32,
+    pub specular: i32,
+    pub transmission: i32,
+    pub volume: i32,
+}
+
+impl Bounces {
+    pub fn after(self, lobe: Lobe) -> Bounces {
+        let mut bounces = self;
+        bounces.total += 1;
+        match lobe {
+            Lobe::Diffuse => bounces.diffuse += 1,
+            Lobe::Specular => bounces.specular += 1,
+            Lobe::Transmission { .. } => bounces.transmission += 1,
+            Lobe::Volume => bounces.volume += 1,
+        }
+        bounces
+    }
+
+    pub fn exceeds(&self, limits: &DepthLimits) -> bool {
+        self.diffuse > limits.diffuse
+            || self.specular > limits.specular
+            || self.transmission > limits.transmission
+            || self.volume > limits.volume
+    }
+}
+
+/// Factor applied to the throughput of paths traced from the lights at a scatter event of kind
+/// `lobe`. Materials leave out the change in radiance across refractive interfaces, so paths
+/// carrying light the other way put it back in to agree with camera paths when an emitter sits
+/// inside a refractive object.
+pub fn light_path_scale(lobe: Lobe) -> Float {
+    match lobe {
+        Lobe::Transmission { eta } => eta * eta,
+        _ => 1.0,
+    }
+}
+
+/// Light from `lights` scattered towards `wo` by the hit, through a shadow ray to one point of
+/// each, in the units of `ray`. Rays cannot hit these lights, so this is the only way their
+/// light enters a path.
+fn direct_light(
+    lights: &[Light],
+    world: &World,
+    ray: &Ray,
+    hit_record: &HitRecord,
+    wo: Vec3,
+) -> Color {
+    let material = hit_record.material.as_ref().unwrap();
+    let mut radiance = Color {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    for light in lights.iter() {
+        let incident = light.sample_incident(hit_record.p);
+        let f = match material.eval(hit_record, wo, incident.wi) {
+            Some(f) if f.x.max(f.y).max(f.z) > 0.0 => f,
+            _ => continue,
+        };
+        if Light::visible(world, ray, hit_record, &incident) {
+            radiance += &upsample(f * incident.irradiance, ray.wavelength);
+        }
+    }
+    radiance
+}
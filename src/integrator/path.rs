@@ -0,0 +1,94 @@
+use crate::{
+    camera::CameraModel,
+    float::Float,
+    helpers::random_float,
+    hittable::World,
+    light::Light,
+    ray::Ray,
+    spectral::upsample,
+    vec3::{unit_vector, Color},
+};
+
+use super::{direct_light, Background, Bounces, DepthLimits, Integrator, Splat};
+
+/// Unidirectional path tracer following one scattered ray per bounce, with the path throughput
+/// carried along explicitly. Since rays cannot hit `lights`, their light is added at every
+/// vertex whose material can be evaluated, through a shadow ray to one point of each.
+pub struct PathTracer {
+    pub lights: Vec<Light>,
+    pub depths: DepthLimits,
+    /// Upper bound on the brightest channel of a single sample, trading a little energy for
+    /// fewer fireflies from caustic paths.
+    pub clamp: Option<Float>,
+    pub background: Background,
+}
+
+impl PathTracer {
+    fn clamped(&self, radiance: Color) -> Color {
+        let peak = radiance.x.max(radiance.y).max(radiance.z);
+        match self.clamp {
+            Some(max) if peak > max => (max / peak) * radiance,
+            _ => radiance,
+        }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(
+        &self,
+        ray: &Ray,
+        world: &World,
+        _camera: &dyn CameraModel,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        let mut radiance = Color {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let mut ray = *ray;
+        let mut throughput = Color {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        let mut bounces = Bounces::default();
+        loop {
+            if bounces.total >= self.depths.total {
+                return self.clamped(radiance);
+            }
+            let hit_record = match world.hit(&ray, 0.0, Float::MAX) {
+                Some(hit_record) => hit_record,
+                None => {
+                    let sky = upsample(self.background.radiance(&ray), ray.wavelength);
+                    return self.clamped(radiance + throughput * sky);
+                }
+            };
+            let material = hit_record.material.as_ref().unwrap();
+            let emitted = upsample(material.emitted(&hit_record), ray.wavelength);
+            radiance += &(throughput * emitted);
+            if !self.lights.is_empty() {
+                let wo = -unit_vector(ray.direction);
+                let direct = direct_light(&self.lights, world, &ray, &hit_record, wo);
+                radiance += &(throughput * direct);
+            }
+            let (attenuation, scattered, lobe) = match material.scatter(&ray, &hit_record) {
+                Some(scatter) => scatter,
+                None => return self.clamped(radiance),
+            };
+            bounces = bounces.after(lobe);
+            if bounces.exceeds(&self.depths) {
+                return self.clamped(radiance);
+            }
+            throughput = throughput * upsample(attenuation, ray.wavelength);
+            let survival = self
+                .depths
+                .survival_probability(bounces, throughput.x.max(throughput.y).max(throughput.z));
+            if random_float() >= survival {
+                return self.clamped(radiance);
+            }
+            throughput /= survival;
+            ray = scattered;
+        }
+    }
+}
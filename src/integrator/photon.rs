@@ -0,0 +1,318 @@
+use crate::{
+    camera::CameraModel,
+    float::{consts::PI, Float},
+    helpers::{random_float, random_in_unit_disk, random_in_unit_sphere},
+    hittable::{HitRecord, World},
+    light::Light,
+    ray::Ray,
+    spectral::upsample,
+    vec3::{dot_product, orthonormal_basis, unit_vector, Color, Point3, Vec3},
+};
+
+use super::{
+    area_lights::{AreaLights, EmissionSample},
+    direct_light, light_path_scale,
+    photon_map::{Photon, PhotonMap},
+    Background, Bounces, DepthLimits, Integrator, Splat,
+};
+
+/// Rate at which progressive passes shrink the gather radius, `α` in Knaus and Zwicker's
+/// probabilistic formulation of progressive photon mapping.
+const RADIUS_REDUCTION: Float = 2.0 / 3.0;
+
+/// Sphere bounding the part of a scene that sky light can be focused by.
+#[derive(Copy, Clone)]
+pub struct BoundingSphere {
+    pub center: Point3,
+    pub radius: Float,
+}
+
+impl BoundingSphere {
+    fn contains(&self, p: Point3) -> bool {
+        (p - self.center).len_squared() <= self.radius * self.radius
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct PhotonSettings {
+    /// Number of photons emitted from every kind of light in each pass.
+    pub photons: usize,
+    /// Gather radius of the first pass.
+    pub radius: Float,
+    pub passes: usize,
+}
+
+impl Default for PhotonSettings {
+    fn default() -> PhotonSettings {
+        PhotonSettings {
+            photons: 200_000,
+            radius: 0.05,
+            passes: 8,
+        }
+    }
+}
+
+/// Path tracer that leaves caustics, light reaching a diffuse surface only through specular
+/// ones, to photon maps.
+///
+/// Photons are traced from the area lights and, when `sky_bounds` is given, from the background
+/// through a disk facing them, and are stored where they first land on a diffuse surface after
+/// at least one specular bounce. Camera paths estimate the density of these photons at every
+/// diffuse vertex and drop the light they would otherwise pick up along the same kind of paths.
+/// Materials with ideal `specular` rays, or without `eval`, count as specular. Photons carry RGB
+/// power and are traced at `time`, so an animated scene needs a new `PhotonMapper` for every
+/// frame.
+///
+/// `lights` emit no photons. Like `PathTracer`, camera paths pick up their light through shadow
+/// rays, so they light surfaces directly but cast no caustics.
+///
+/// With several passes, each one is traced with a smaller gather radius than the last and every
+/// camera sample uses a random pass, so the average converges to the unbiased result as the
+/// number of passes grows.
+pub struct PhotonMapper {
+    pub lights: Vec<Light>,
+    pub depths: DepthLimits,
+    pub background: Background,
+    area_lights: AreaLights,
+    sky_bounds: Option<BoundingSphere>,
+    time: Float,
+    /// Caustic photon map and gather radius of every pass.
+    passes: Vec<(PhotonMap, Float)>,
+}
+
+impl PhotonMapper {
+    pub fn new(
+        world: &World,
+        lights: Vec<Light>,
+        depths: DepthLimits,
+        background: Background,
+        sky_bounds: Option<BoundingSphere>,
+        settings: PhotonSettings,
+        time: Float,
+    ) -> PhotonMapper {
+        let mut photon_mapper = PhotonMapper {
+            lights,
+            depths,
+            background,
+            area_lights: AreaLights::new(world),
+            sky_bounds,
+            time,
+            passes: vec![],
+        };
+        let mut radius_squared = settings.radius * settings.radius;
+        for pass in 1..=settings.passes.max(1) {
+            let photons = photon_mapper.trace_photons(world, settings.photons.max(1));
+            photon_mapper
+                .passes
+                .push((PhotonMap::new(photons), radius_squared.sqrt()));
+            radius_squared *= (pass as Float + RADIUS_REDUCTION) / (pass as Float + 1.0);
+        }
+        photon_mapper
+    }
+
+    /// Emits `count` photons from the area lights and as many from the sky.
+    fn trace_photons(&self, world: &World, count: usize) -> Vec<Photon> {
+        let mut photons = vec![];
+        for _ in 0..count {
+            if let Some(EmissionSample {
+                record,
+                pdf_position,
+                direction,
+                ..
+            }) = self.area_lights.sample_emission(world)
+            {
+                let emitted = record.material.as_ref().unwrap().emitted(&record);
+                let ray = Ray {
+                    origin: record.spawn_origin(direction),
+                    direction,
+                    time: self.time,
+                    wavelength: None,
+                };
+                let power = (PI / (pdf_position * count as Float)) * emitted;
+                self.trace_photon(world, ray, power, &mut photons);
+            }
+            if let Some(bounds) = self.sky_bounds {
+                let to_sky = unit_vector(random_in_unit_sphere());
+                let (a, b) = orthonormal_basis(to_sky);
+                let disk = random_in_unit_disk();
+                let origin = bounds.center + bounds.radius * (to_sky + disk.x * a + disk.y * b);
+                let sky_ray = Ray {
+                    origin,
+                    direction: to_sky,
+                    time: self.time,
+                    wavelength: None,
+                };
+                if world.hit(&sky_ray, 0.0, Float::MAX).is_some() {
+                    continue;
+                }
+                let disk_area = PI * bounds.radius * bounds.radius;
+                let power =
+                    (4.0 * PI * disk_area / count as Float) * self.background.radiance(&sky_ray);
+                let ray = Ray {
+                    direction: -to_sky,
+                    ..sky_ray
+                };
+                self.trace_photon(world, ray, power, &mut photons);
+            }
+        }
+        photons
+    }
+
+    /// Follows a photon through specular bounces, storing it where it lands on a diffuse
+    /// surface after at least one of them.
+    fn trace_photon(&self, world: &World, ray: Ray, power: Color, photons: &mut Vec<Photon>) {
+        let mut ray = ray;
+        let mut throughput = Color {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        let mut bounces = Bounces::default();
+        while bounces.total < self.depths.total {
+            let hit_record = match world.hit(&ray, 0.0, Float::MAX) {
+                Some(hit_record) => hit_record,
+                None => return,
+            };
+            let material = hit_record.material.as_ref().unwrap();
+            let wi = -unit_vector(ray.direction);
+            if is_diffuse(&ray, &hit_record) {
+                if bounces.total > 0 {
+                    photons.push(Photon {
+                        position: hit_record.p,
+                        wi,
+                        power: throughput * power,
+                    });
+                }
+                return;
+            }
+            let (attenuation, scattered, lobe) = match material.scatter(&ray, &hit_record) {
+                Some(scatter) => scatter,
+                None => return,
+            };
+            bounces = bounces.after(lobe);
+            if bounces.exceeds(&self.depths) {
+                return;
+            }
+            throughput = light_path_scale(lobe) * (throughput * attenuation);
+            let survival = self
+                .depths
+                .survival_probability(bounces, throughput.x.max(throughput.y).max(throughput.z));
+            if random_float() >= survival {
+                return;
+            }
+            throughput /= survival;
+            ray = scattered;
+        }
+    }
+
+    /// Radiance reflected towards `wo` by the caustic photons around a diffuse hit.
+    fn caustics(&self, map: &PhotonMap, radius: Float, hit_record: &HitRecord, wo: Vec3) -> Color {
+        let material = hit_record.material.as_ref().unwrap();
+        let mut reflected = Color {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        map.within(hit_record.p, radius, |photon| {
+            let cos_theta = dot_product(hit_record.normal, photon.wi);
+            if cos_theta <= 0.0 {
+                return;
+            }
+            if let Some(f) = material.eval(hit_record, wo, photon.wi) {
+                reflected += &((1.0 / cos_theta) * (f * photon.power));
+            }
+        });
+        (1.0 / (PI * radius * radius)) * reflected
+    }
+}
+
+/// Whether photons can be stored at the hit of `ray`, which is the case for materials that can
+/// be evaluated for arbitrary pairs of directions and that renderers do not follow through
+/// ideal specular rays, the way `Whitted` shades them.
+fn is_diffuse(ray: &Ray, hit_record: &HitRecord) -> bool {
+    let material = hit_record.material.as_ref().unwrap();
+    let w = -unit_vector(ray.direction);
+    material.eval(hit_record, w, w).is_some() && material.specular(ray, hit_record).is_empty()
+}
+
+impl Integrator for PhotonMapper {
+    fn radiance(
+        &self,
+        ray: &Ray,
+        world: &World,
+        _camera: &dyn CameraModel,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        let pass =
+            ((random_float() * self.passes.len() as Float) as usize).min(self.passes.len() - 1);
+        let (map, radius) = &self.passes[pass];
+        let mut radiance = Color {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let mut ray = *ray;
+        let mut throughput = Color {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        let mut bounces = Bounces::default();
+        // Last specular vertex, while the path has only gone through specular vertices since its
+        // last diffuse one. Light picked up from there on is what the photon maps account for.
+        let mut caustic_vertex: Option<Point3> = None;
+        let mut after_diffuse = false;
+        loop {
+            if bounces.total >= self.depths.total {
+                return radiance;
+            }
+            let hit_record = match world.hit(&ray, 0.0, Float::MAX) {
+                Some(hit_record) => hit_record,
+                None => {
+                    let from_photons = match (caustic_vertex, self.sky_bounds) {
+                        (Some(p), Some(bounds)) => bounds.contains(p),
+                        _ => false,
+                    };
+                    if !from_photons {
+                        let sky = upsample(self.background.radiance(&ray), ray.wavelength);
+                        radiance += &(throughput * sky);
+                    }
+                    return radiance;
+                }
+            };
+            let material = hit_record.material.as_ref().unwrap();
+            if caustic_vertex.is_none() || self.area_lights.origin_pdf(&hit_record) == 0.0 {
+                let emitted = upsample(material.emitted(&hit_record), ray.wavelength);
+                radiance += &(throughput * emitted);
+            }
+            let wo = -unit_vector(ray.direction);
+            let direct = direct_light(&self.lights, world, &ray, &hit_record, wo);
+            radiance += &(throughput * direct);
+            if is_diffuse(&ray, &hit_record) {
+                let caustics = self.caustics(map, *radius, &hit_record, wo);
+                radiance += &(throughput * upsample(caustics, ray.wavelength));
+                after_diffuse = true;
+                caustic_vertex = None;
+            } else if after_diffuse {
+                caustic_vertex = Some(hit_record.p);
+            }
+            let (attenuation, scattered, lobe) = match material.scatter(&ray, &hit_record) {
+                Some(scatter) => scatter,
+                None => return radiance,
+            };
+            bounces = bounces.after(lobe);
+            if bounces.exceeds(&self.depths) {
+                return radiance;
+            }
+            throughput = throughput * upsample(attenuation, ray.wavelength);
+            let survival = self
+                .depths
+                .survival_probability(bounces, throughput.x.max(throughput.y).max(throughput.z));
+            if random_float() >= survival {
+                return radiance;
+            }
+            throughput /= survival;
+            ray = scattered;
+        }
+    }
+}
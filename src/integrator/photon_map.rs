@@ -0,0 +1,105 @@
+use std::cmp::Ordering;
+
+use crate::{
+    float::Float,
+    vec3::{Color, Point3, Vec3},
+};
+
+/// Light flux deposited on a surface by a path traced from a light.
+#[derive(Copy, Clone)]
+pub struct Photon {
+    pub position: Point3,
+    /// Unit vector pointing back along the direction the photon arrived from.
+    pub wi: Vec3,
+    pub power: Color,
+}
+
+struct Node {
+    photon: Photon,
+    /// Coordinate the node splits its subtree along.
+    axis: usize,
+}
+
+/// Balanced kd-tree of photons stored implicitly in a vector: the node of every subtree sits in
+/// the middle of its slice, with the nodes below it on either side.
+pub struct PhotonMap {
+    nodes: Vec<Node>,
+}
+
+impl PhotonMap {
+    pub fn new(photons: Vec<Photon>) -> PhotonMap {
+        let mut nodes: Vec<Node> = photons
+            .into_iter()
+            .map(|photon| Node { photon, axis: 0 })
+            .collect();
+        PhotonMap::build(&mut nodes);
+        PhotonMap { nodes }
+    }
+
+    /// Splits `nodes` at the median along the axis they are most spread over.
+    fn build(nodes: &mut [Node]) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mut min = [Float::MAX; 3];
+        let mut max = [Float::MIN; 3];
+        for node in nodes.iter() {
+            for axis in 0..3 {
+                let value = component(node.photon.position, axis);
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+        let axis = (0..3)
+            .max_by(|a, b| {
+                (max[*a] - min[*a])
+                    .partial_cmp(&(max[*b] - min[*b]))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .unwrap();
+        let middle = nodes.len() / 2;
+        nodes.select_nth_unstable_by(middle, |a, b| {
+            component(a.photon.position, axis)
+                .partial_cmp(&component(b.photon.position, axis))
+                .unwrap_or(Ordering::Equal)
+        });
+        nodes[middle].axis = axis;
+        let (below, above) = nodes.split_at_mut(middle);
+        PhotonMap::build(below);
+        PhotonMap::build(&mut above[1..]);
+    }
+
+    /// Calls `f` with every photon closer than `radius` to `center`.
+    pub fn within(&self, center: Point3, radius: Float, mut f: impl FnMut(&Photon)) {
+        PhotonMap::visit(&self.nodes, center, radius * radius, &mut f);
+    }
+
+    fn visit(nodes: &[Node], center: Point3, radius_squared: Float, f: &mut impl FnMut(&Photon)) {
+        if nodes.is_empty() {
+            return;
+        }
+        let middle = nodes.len() / 2;
+        let node = &nodes[middle];
+        if (node.photon.position - center).len_squared() <= radius_squared {
+            f(&node.photon);
+        }
+        let offset = component(center, node.axis) - component(node.photon.position, node.axis);
+        let (near, far) = if offset < 0.0 {
+            (&nodes[..middle], &nodes[middle + 1..])
+        } else {
+            (&nodes[middle + 1..], &nodes[..middle])
+        };
+        PhotonMap::visit(near, center, radius_squared, f);
+        if offset * offset <= radius_squared {
+            PhotonMap::visit(far, center, radius_squared, f);
+        }
+    }
+}
+
+fn component(v: Vec3, axis: usize) -> Float {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
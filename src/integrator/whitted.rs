@@ -0,0 +1,100 @@
+use crate::{
+    camera::CameraModel,
+    float::{consts::PI, Float},
+    hittable::{HitRecord, World},
+    light::Light,
+    ray::Ray,
+    spectral::upsample,
+    vec3::{dot_product, unit_vector, Color},
+};
+
+use super::{Background, Integrator, Splat};
+
+/// Weight below which a specular branch cannot visibly change a pixel and is dropped.
+const MIN_WEIGHT: Float = 1.0 / 256.0;
+
+/// Deterministic, noise free preview in the style of Whitted: ideal reflection and refraction
+/// are followed recursively, and every other surface is lit directly by `lights` with hard
+/// shadows cast from their centers. Surfaces without a BSDF to evaluate are shaded as diffuse
+/// with their albedo.
+pub struct Whitted {
+    pub lights: Vec<Light>,
+    pub background: Background,
+    /// Uniform light added to that of the background in the direction of the normal, so
+    /// surfaces in shadow keep some of their color.
+    pub ambient: Float,
+    pub max_depth: i32,
+}
+
+impl Whitted {
+    fn trace(&self, world: &World, ray: &Ray, weight: Color, depth: i32) -> Color {
+        if depth >= self.max_depth || weight.x.max(weight.y).max(weight.z) < MIN_WEIGHT {
+            return BLACK;
+        }
+        let hit_record = match world.hit(ray, 0.0, Float::MAX) {
+            Some(hit_record) => hit_record,
+            None => return self.background.radiance(ray),
+        };
+        let material = hit_record.material.as_ref().unwrap();
+        let emitted = material.emitted(&hit_record);
+        let specular = material.specular(ray, &hit_record);
+        if specular.is_empty() {
+            return emitted + self.shade(world, ray, &hit_record);
+        }
+        specular
+            .iter()
+            .fold(emitted, |color, (attenuation, scattered)| {
+                color
+                    + *attenuation * self.trace(world, scattered, weight * *attenuation, depth + 1)
+            })
+    }
+
+    /// Direct light from `lights` and ambient light reflected along `ray` by a diffuse hit.
+    fn shade(&self, world: &World, ray: &Ray, hit_record: &HitRecord) -> Color {
+        let material = hit_record.material.as_ref().unwrap();
+        let albedo = material.albedo(hit_record);
+        let wo = -unit_vector(ray.direction);
+        let to_normal = Ray {
+            origin: hit_record.p,
+            direction: hit_record.normal,
+            time: ray.time,
+            wavelength: ray.wavelength,
+        };
+        let ambient = self.background.radiance(&to_normal) + self.ambient * WHITE;
+        self.lights.iter().fold(albedo * ambient, |color, light| {
+            let incident = light.incident(hit_record.p);
+            let cos_theta = dot_product(hit_record.normal, incident.wi);
+            if cos_theta <= 0.0 || !Light::visible(world, ray, hit_record, &incident) {
+                return color;
+            }
+            let f = material
+                .eval(hit_record, wo, incident.wi)
+                .unwrap_or((cos_theta / PI) * albedo);
+            color + f * incident.irradiance
+        })
+    }
+}
+
+impl Integrator for Whitted {
+    fn radiance(
+        &self,
+        ray: &Ray,
+        world: &World,
+        _camera: &dyn CameraModel,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        upsample(self.trace(world, ray, WHITE, 0), ray.wavelength)
+    }
+}
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+const WHITE: Color = Color {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+};
@@ -0,0 +1,38 @@
+//! Physically based ray tracer. Build a `Scene`, or pick one of the demo `scenes`, and pass it
+//! to `render` with the `RenderSettings` to render it with.
+#![allow(clippy::needless_return)]
+
+pub mod animation;
+mod aov;
+mod benchmark;
+pub mod camera;
+pub mod diff;
+pub mod float;
+mod helpers;
+pub mod hittable;
+pub mod image;
+pub mod integrator;
+pub mod light;
+pub mod material;
+pub mod png;
+pub mod ppm;
+pub mod ray;
+pub mod render;
+pub mod scene;
+pub mod scenes;
+pub mod settings;
+mod simd;
+mod spectral;
+pub mod texture;
+pub mod vec3;
+
+pub use aov::AovBuffers;
+pub use benchmark::run as run_benchmark;
+pub use camera::{Camera, CameraError, CameraModel, SharedCamera};
+pub use helpers::{random_float, random_in_unit_disk, random_in_unit_sphere, seed_random};
+pub use hittable::{BoxedHittable, Hittable, World};
+pub use image::Image;
+pub use material::{Material, SharedMaterial};
+pub use render::{render, render_frame, Frame};
+pub use scene::{Scene, View};
+pub use settings::RenderSettings;
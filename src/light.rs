@@ -0,0 +1,175 @@
+use crate::{
+    float::{consts::PI, Float},
+    helpers::random_float,
+    hittable::{HitRecord, World},
+    ray::Ray,
+    vec3::{dot_product, orthonormal_basis, unit_vector, Color, Point3, Vec3},
+};
+
+/// How the light of a point or spot light weakens with distance.
+#[derive(Copy, Clone)]
+pub enum Falloff {
+    /// Constant, regardless of distance.
+    None,
+    /// Inversely proportional to the distance.
+    Linear,
+    /// Inversely proportional to the square of the distance, as for a physical light.
+    InverseSquare,
+}
+
+impl Falloff {
+    fn attenuation(&self, distance: Float) -> Float {
+        match self {
+            Falloff::None => 1.0,
+            Falloff::Linear => 1.0 / distance,
+            Falloff::InverseSquare => 1.0 / (distance * distance),
+        }
+    }
+}
+
+/// Light source without a surface, found only by casting shadow rays towards it.
+#[derive(Copy, Clone)]
+pub enum Light {
+    /// Radiates `intensity` equally in all directions.
+    Point {
+        position: Point3,
+        intensity: Color,
+        falloff: Falloff,
+    },
+    /// Point light restricted to a cone around `direction`. It is at full `intensity` within
+    /// `inner_angle` of the axis and fades out smoothly towards `outer_angle`, both half angles
+    /// in radians.
+    Spot {
+        position: Point3,
+        direction: Vec3,
+        intensity: Color,
+        falloff: Falloff,
+        inner_angle: Float,
+        outer_angle: Float,
+    },
+    /// Parallel light, like the sun, travelling along `direction` and delivering `irradiance` to
+    /// surfaces facing it. A non-zero `angular_diameter`, in radians, spreads the directions
+    /// it arrives from over a disk on the sky and softens its shadows.
+    Directional {
+        direction: Vec3,
+        irradiance: Color,
+        angular_diameter: Float,
+    },
+}
+
+/// Light arriving at a point from a `Light`.
+pub struct Incident {
+    /// Unit vector from the point towards the light.
+    pub wi: Vec3,
+    /// Distance to the light, infinite for directional lights.
+    pub distance: Float,
+    /// Irradiance on a surface perpendicular to `wi`.
+    pub irradiance: Color,
+}
+
+impl Light {
+    /// Light arriving at `p` from the center of the light, for renderers that need to be
+    /// deterministic.
+    pub fn incident(&self, p: Point3) -> Incident {
+        match self {
+            Light::Point {
+                position,
+                intensity,
+                falloff,
+            } => {
+                let to_light = *position - p;
+                let distance = to_light.len();
+                Incident {
+                    wi: to_light / distance,
+                    distance,
+                    irradiance: falloff.attenuation(distance) * *intensity,
+                }
+            }
+            Light::Spot {
+                position,
+                direction,
+                intensity,
+                falloff,
+                inner_angle,
+                outer_angle,
+            } => {
+                let to_light = *position - p;
+                let distance = to_light.len();
+                let wi = to_light / distance;
+                let cos_theta = dot_product(-wi, unit_vector(*direction));
+                let edge = smoothstep(outer_angle.cos(), inner_angle.cos(), cos_theta);
+                Incident {
+                    wi,
+                    distance,
+                    irradiance: (edge * falloff.attenuation(distance)) * *intensity,
+                }
+            }
+            Light::Directional {
+                direction,
+                irradiance,
+                ..
+            } => Incident {
+                wi: -unit_vector(*direction),
+                distance: Float::INFINITY,
+                irradiance: *irradiance,
+            },
+        }
+    }
+
+    /// Like `incident`, but picks the direction of lights with an angular extent uniformly
+    /// within it.
+    pub fn sample_incident(&self, p: Point3) -> Incident {
+        match self {
+            Light::Directional {
+                direction,
+                irradiance,
+                angular_diameter,
+            } if *angular_diameter > 0.0 => {
+                let axis = -unit_vector(*direction);
+                let (tangent, bitangent) = orthonormal_basis(axis);
+                let cos_max = (angular_diameter / 2.0).cos();
+                let cos_theta = 1.0 - random_float() * (1.0 - cos_max);
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * random_float();
+                Incident {
+                    wi: unit_vector(
+                        cos_theta * axis
+                            + (sin_theta * phi.cos()) * tangent
+                            + (sin_theta * phi.sin()) * bitangent,
+                    ),
+                    distance: Float::INFINITY,
+                    irradiance: *irradiance,
+                }
+            }
+            _ => self.incident(p),
+        }
+    }
+
+    /// Whether nothing in `world` blocks the light of `incident` from reaching the point of
+    /// `hit_record`, with the time and wavelength of the shadow ray taken from `ray`. The shadow
+    /// ray leaves the surface like any other ray and, for lights at a finite distance, ends at
+    /// the light itself, which has no surface to stop short of.
+    pub fn visible(world: &World, ray: &Ray, hit_record: &HitRecord, incident: &Incident) -> bool {
+        if incident.distance.is_infinite() {
+            let shadow_ray = hit_record.spawn_ray(ray, incident.wi);
+            return world.hit(&shadow_ray, 0.0, Float::MAX).is_none();
+        }
+        let target = hit_record.p + incident.distance * incident.wi;
+        let origin = hit_record.spawn_origin(incident.wi);
+        let shadow_ray = Ray {
+            origin,
+            direction: target - origin,
+            time: ray.time,
+            wavelength: ray.wavelength,
+        };
+        world.hit(&shadow_ray, 0.0, 1.0).is_none()
+    }
+}
+
+fn smoothstep(edge0: Float, edge1: Float, x: Float) -> Float {
+    if edge1 <= edge0 {
+        return if x >= edge1 { 1.0 } else { 0.0 };
+    }
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -1,222 +1,116 @@
-use std::{
-    error,
-    sync::{Arc, Mutex},
-    thread::available_parallelism,
-};
-
-use camera::Camera;
-use helpers::{random_f32, random_f32_in_range};
-use hittable::{Hittable, Sphere};
-use material::{Dielectric, Lambertian, Material, Metal};
-use vec3::{Color, Point3, Vec3};
-
-use crate::ppm::{generate_ppm, save_ppm};
-mod camera;
-mod helpers;
-mod hittable;
-mod material;
-mod ppm;
-mod ray;
-mod vec3;
-
-fn random_scene() -> Arc<Vec<Box<dyn Hittable + Sync + Send>>> {
-    let mut world: Vec<Box<dyn Hittable + Sync + Send>> = vec![];
-
-    let ground_material: Arc<Box<dyn Material + Send + Sync>> = Arc::new(Box::new(Lambertian {
-        albedo: Color {
-            x: 0.5,
-            y: 0.5,
-            z: 0.5,
-        },
-    }));
+use std::error;
 
-    world.push(Box::new(Sphere {
-        center: Point3 {
-            x: 0.0,
-            y: -1000.0,
-            z: 0.0,
-        },
-        radius: 1000.0,
-        material: Arc::clone(&ground_material),
-    }));
-
-    let material1: Arc<Box<dyn Material + Send + Sync>> =
-        Arc::new(Box::new(Dielectric { ir: 1.5 }));
-    world.push(Box::new(Sphere {
-        center: Point3 {
-            x: 0.0,
-            y: 1.0,
-            z: 0.0,
-        },
-        radius: 1.0,
-        material: material1,
-    }));
+use rust_raytracer::{
+    diff,
+    float::Float,
+    png::{generate_png, generate_png_linear, save_png},
+    ppm::{generate_ppm, generate_ppm_linear, read_ppm_srgb, save_ppm},
+    render::{build_camera, build_integrator},
+    render_frame, run_benchmark, scenes,
+    settings::{DiffSettings, IntegratorKind},
+    Frame, RenderSettings,
+};
 
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = random_f32();
-            let center = Point3 {
-                x: (a as f32) + 0.9 * random_f32(),
-                y: 0.2,
-                z: (b as f32) + 0.9 * random_f32(),
-            };
+/// File format of rendered images: PPM for a single image, PNG for the frames of a sequence.
+#[derive(Copy, Clone)]
+enum Format {
+    Ppm,
+    Png,
+}
 
-            if (center
-                - Point3 {
-                    x: 4.0,
-                    y: 0.2,
-                    z: 0.0,
-                })
-            .len()
-                > 0.9
-            {
-                let material: Box<dyn Material + Send + Sync> = if choose_mat < 0.8 {
-                    Box::new(Lambertian {
-                        albedo: Color::random(),
-                    })
-                } else if choose_mat < 0.95 {
-                    Box::new(Metal {
-                        albedo: Color::random(),
-                        fuzzines: random_f32_in_range(0.0, 0.5),
-                    })
-                } else {
-                    Box::new(Dielectric { ir: 1.5 })
-                };
-                world.push(Box::new(Sphere {
-                    center,
-                    material: Arc::new(material),
-                    radius: 0.2,
-                }))
-            }
+fn save_frame(settings: &RenderSettings, name: &str, frame: &Frame, format: Format) {
+    // Debug views hold values to look at directly rather than radiance to tone map.
+    let linear = matches!(settings.integrator, IntegratorKind::Debug(_));
+    let result = match (format, linear) {
+        (Format::Ppm, true) => {
+            save_ppm(&generate_ppm_linear(&frame.image), &format!("{}.ppm", name))
         }
+        (Format::Ppm, false) => save_ppm(&generate_ppm(&frame.image), &format!("{}.ppm", name)),
+        (Format::Png, true) => {
+            save_png(&generate_png_linear(&frame.image), &format!("{}.png", name))
+        }
+        (Format::Png, false) => save_png(&generate_png(&frame.image), &format!("{}.png", name)),
+    };
+    match result {
+        Ok(_r) => println!("File saved!"),
+        Err(_e) => println!("Error saving the file"),
     }
+    if settings.aovs {
+        match frame.aovs.save(name) {
+            Ok(_r) => println!("AOVs saved!"),
+            Err(_e) => println!("Error saving the AOVs"),
+        }
+    }
+}
 
-    let material2: Arc<Box<dyn Material + Send + Sync>> = Arc::new(Box::new(Lambertian {
-        albedo: Color {
-            x: 0.4,
-            y: 0.2,
-            z: 0.1,
-        },
-    }));
-    world.push(Box::new(Sphere {
-        center: Point3 {
-            x: -4.0,
-            y: 1.0,
-            z: 0.0,
-        },
-        radius: 1.0,
-        material: material2,
-    }));
-
-    let material3: Arc<Box<dyn Material + Send + Sync>> = Arc::new(Box::new(Metal {
-        albedo: Color {
-            x: 0.7,
-            y: 0.6,
-            z: 0.5,
-        },
-        fuzzines: 0.0,
-    }));
-    world.push(Box::new(Sphere {
-        center: Point3 {
-            x: 4.0,
-            y: 1.0,
-            z: 0.0,
-        },
-        radius: 1.0,
-        material: material3,
-    }));
-
-    return Arc::new(world);
+/// Prints how far `settings.test` is from `settings.reference` and writes the heatmap of their
+/// differences, failing when they differ by more than `settings.max_flip`.
+fn diff_images(settings: &DiffSettings) -> Result<(), Box<dyn error::Error>> {
+    let reference = read_ppm_srgb(&settings.reference)?;
+    let test = read_ppm_srgb(&settings.test)?;
+    let comparison = diff::compare(&reference, &test)?;
+    println!("RMSE {:.6}", comparison.rmse);
+    println!("PSNR {:.2} dB", comparison.psnr);
+    println!("SSIM {:.6}", comparison.ssim);
+    println!("FLIP {:.6}", comparison.flip);
+    save_ppm(
+        generate_ppm_linear(&comparison.heatmap()).as_str(),
+        &settings.heatmap,
+    )?;
+    if let Some(max_flip) = settings.max_flip {
+        if comparison.flip > max_flip {
+            return Err(format!("FLIP {} exceeds {}", comparison.flip, max_flip).into());
+        }
+    }
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
     let args: Vec<String> = std::env::args().collect();
-    let image_width = if args.len() >= 2 {
-        match args[1].parse::<i32>() {
-            Ok(i) => i,
-            Err(_i) => 400,
-        }
-    } else {
-        400
-    };
-    let aspect_ratio: f32 = 16.0 / 9.0;
-    let image_height = ((image_width as f32) / aspect_ratio) as i32;
-    let samples_per_pixel = 500;
-    let look_from = Point3 {
-        x: 13.0,
-        y: 2.0,
-        z: 3.0,
-    };
-    let look_at = Point3 {
-        x: 0.0,
-        y: 0.0,
-        z: 0.0,
-    };
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
-    let camera = Camera::new(
-        look_from,
-        look_at,
-        Vec3 {
-            x: 0.0,
-            y: 1.0,
-            z: 0.0,
-        },
-        20.0,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
-    );
-    let max_depth = 50;
-
-    let pixels = Arc::new(Mutex::new(vec![
-        Color {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
+    if args.get(1).map(String::as_str) == Some("diff") {
+        return match DiffSettings::from_args(&args) {
+            Some(settings) => diff_images(&settings),
+            None => Err(
+                "usage: diff <reference.ppm> <test.ppm> [--heatmap <path>] [--max-flip <error>]"
+                    .into(),
+            ),
         };
-        (image_width * image_height) as usize
-    ]));
-
-    let world: Arc<Vec<Box<dyn Hittable + Sync + Send>>> = random_scene();
-    let mut handlers = vec![];
-    let threads = available_parallelism()?.get() as i32;
-    let work_per_thread = image_height / threads;
-    for i in 0..image_width {
-        for t in 0..threads {
-            let world_for_thread = Arc::clone(&world);
-            let pixel_for_thread = Arc::clone(&pixels);
-            handlers.push(std::thread::spawn(move || {
-                for j in t * work_per_thread..(t + 1) * work_per_thread {
-                    let mut color = Color {
-                        x: 0.0,
-                        y: 0.0,
-                        z: 0.0,
-                    };
-                    let index = (image_width * (image_height - j - 1) + i) as usize;
-                    for _ in 0..samples_per_pixel {
-                        let u = ((i as f32) + random_f32()) / ((image_width - 1) as f32);
-                        let v = ((j as f32) + random_f32()) / ((image_height - 1) as f32);
-                        let r = camera.get_ray(u, v);
-                        color += &r.color(&world_for_thread, max_depth);
-                    }
-                    let mut pixels_to_edit = pixel_for_thread.lock().unwrap();
-                    pixels_to_edit[index] = color;
-                }
-            }));
+    }
+    let settings = RenderSettings::from_args(&args);
+    let scene = scenes::from_settings(&settings)?;
+    if settings.benchmark {
+        let camera = build_camera(&settings, &scene, scene.view, 0.0)?;
+        run_benchmark(
+            &scene.world,
+            camera.as_ref(),
+            settings.image_width,
+            settings.image_height(),
+        );
+        return Ok(());
+    }
+    match settings.frames {
+        None => {
+            let camera = build_camera(&settings, &scene, scene.view, 0.0)?;
+            let integrator = build_integrator(&settings, &scene, 0.0);
+            let frame = render_frame(&settings, &scene, &camera, &integrator, 0.0)?;
+            save_frame(&settings, "image", &frame, Format::Ppm);
         }
-
-        while handlers.len() > 0 {
-            if let Some(handler) = handlers.pop() {
-                handler.join().unwrap();
+        Some((first, last)) => {
+            // Every frame shares the objects and their `Bvh`; the camera and whatever the
+            // integrator traces ahead of the camera rays, like photon maps, follow the time.
+            for frame in first..=last {
+                let time = frame as Float;
+                let camera = build_camera(&settings, &scene, scene.view_at(time), time)?;
+                let integrator = build_integrator(&settings, &scene, time);
+                let rendered = render_frame(&settings, &scene, &camera, &integrator, time)?;
+                save_frame(
+                    &settings,
+                    format!("frame_{:04}", frame).as_str(),
+                    &rendered,
+                    Format::Png,
+                );
             }
         }
     }
-    let pixels_to_use = pixels.lock().unwrap();
-    let result = generate_ppm(image_width, image_height, &pixels_to_use, samples_per_pixel);
-    match save_ppm(result.as_str()) {
-        Ok(_r) => println!("File saved!"),
-        Err(_e) => println!("Error saving the file"),
-    }
     Ok(())
 }
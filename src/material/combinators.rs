@@ -0,0 +1,272 @@
+use crate::{
+    float::Float,
+    helpers::random_float,
+    hittable::HitRecord,
+    ray::Ray,
+    texture::SharedTexture,
+    vec3::{dot_product, reflect, unit_vector, Color, Vec3},
+};
+
+use super::{
+    microfacet::{fresnel_dielectric, Frame, Ggx},
+    Lobe, Material, SharedMaterial,
+};
+
+pub enum MixWeight {
+    Scalar(Float),
+    /// Uses the luminance of the texture at the hit point.
+    Texture(SharedTexture),
+}
+
+impl MixWeight {
+    /// Weight at the hit, clamped to `[0, 1]`.
+    fn value(&self, hit_record: &HitRecord) -> Float {
+        let weight = match self {
+            MixWeight::Scalar(weight) => *weight,
+            MixWeight::Texture(texture) => {
+                let c = texture.value(hit_record);
+                0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+            }
+        };
+        weight.clamp(0.0, 1.0)
+    }
+}
+
+/// Stochastic blend of two materials: each scatter event uses `b` with probability `weight`
+/// and `a` otherwise. Renderers following ideal rays get those of both materials, or shade the
+/// blend as diffuse when only one of them has any.
+pub struct Mix {
+    pub a: SharedMaterial,
+    pub b: SharedMaterial,
+    pub weight: MixWeight,
+}
+
+impl Material for Mix {
+    fn albedo(&self, hit_record: &HitRecord) -> Color {
+        let weight = self.weight.value(hit_record);
+        (1.0 - weight) * self.a.albedo(hit_record) + weight * self.b.albedo(hit_record)
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        if random_float() < self.weight.value(hit_record) {
+            self.b.scatter(ray, hit_record)
+        } else {
+            self.a.scatter(ray, hit_record)
+        }
+    }
+
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        let weight = self.weight.value(hit_record);
+        let a = self.a.eval(hit_record, wo, wi)?;
+        let b = self.b.eval(hit_record, wo, wi)?;
+        Some((1.0 - weight) * a + weight * b)
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Float {
+        let weight = self.weight.value(hit_record);
+        (1.0 - weight) * self.a.pdf(hit_record, wo, wi) + weight * self.b.pdf(hit_record, wo, wi)
+    }
+
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        let weight = self.weight.value(hit_record);
+        (1.0 - weight) * self.a.emitted(hit_record) + weight * self.b.emitted(hit_record)
+    }
+
+    fn specular(&self, ray: &Ray, hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        let weight = self.weight.value(hit_record);
+        if weight == 0.0 {
+            return self.a.specular(ray, hit_record);
+        }
+        if weight == 1.0 {
+            return self.b.specular(ray, hit_record);
+        }
+        let a = self.a.specular(ray, hit_record);
+        let b = self.b.specular(ray, hit_record);
+        if a.is_empty() || b.is_empty() {
+            return vec![];
+        }
+        a.into_iter()
+            .map(|(attenuation, ray)| ((1.0 - weight) * attenuation, ray))
+            .chain(
+                b.into_iter()
+                    .map(|(attenuation, ray)| (weight * attenuation, ray)),
+            )
+            .collect()
+    }
+}
+
+/// Clear dielectric coat, like varnish or lacquer, over any base material. Light either
+/// reflects off the coat or passes through it twice, tinted by `tint` on each pass, around a
+/// scatter event of `base`. The coat is picked with the Fresnel reflectance of the macro
+/// surface, which also dims the light reaching the base, and reflects with that of its
+/// microfacets. Renderers following ideal rays get the mirror reflection of the coat along with
+/// those of the base, or shade the base as diffuse when it has none.
+pub struct Coated {
+    pub base: SharedMaterial,
+    pub ir: Float,
+    pub roughness: Float,
+    pub tint: Color,
+}
+
+impl Coated {
+    fn distribution(&self) -> Ggx {
+        Ggx::from_roughness(self.roughness, self.roughness)
+    }
+}
+
+impl Material for Coated {
+    fn albedo(&self, hit_record: &HitRecord) -> Color {
+        self.base.albedo(hit_record)
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        if !hit_record.front_face {
+            return self.base.scatter(ray, hit_record);
+        }
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(-unit_vector(ray.direction));
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let reflectance = fresnel_dielectric(wo.z, self.ir);
+        if random_float() < reflectance {
+            let distribution = self.distribution();
+            let h = distribution.sample_visible_normal(wo);
+            let wi = 2.0 * dot_product(wo, h) * h - wo;
+            if wi.z <= 0.0 {
+                return None;
+            }
+            let weight = fresnel_dielectric(dot_product(wo, h), self.ir) / reflectance
+                * distribution.g(wo, wi)
+                / distribution.g1(wo);
+            return Some((
+                weight * WHITE,
+                hit_record.spawn_ray(ray, frame.to_world(wi)),
+                Lobe::Specular,
+            ));
+        }
+        let (attenuation, scattered, lobe) = self.base.scatter(ray, hit_record)?;
+        Some((self.tint * self.tint * attenuation, scattered, lobe))
+    }
+
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        let base = self.base.eval(hit_record, wo, wi)?;
+        if !hit_record.front_face {
+            return Some(base);
+        }
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(wo);
+        if wo.z <= 0.0 {
+            return Some(BLACK);
+        }
+        let coat = match self.distribution().reflection(wo, frame.to_local(wi)) {
+            Some((h, value, _)) => fresnel_dielectric(dot_product(wo, h), self.ir) * value,
+            None => 0.0,
+        };
+        let reflectance = fresnel_dielectric(wo.z, self.ir);
+        Some(coat * WHITE + (1.0 - reflectance) * (self.tint * self.tint * base))
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Float {
+        let base = self.base.pdf(hit_record, wo, wi);
+        if !hit_record.front_face {
+            return base;
+        }
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(wo);
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+        let coat = self
+            .distribution()
+            .reflection(wo, frame.to_local(wi))
+            .map_or(0.0, |(_, _, pdf)| pdf);
+        let reflectance = fresnel_dielectric(wo.z, self.ir);
+        reflectance * coat + (1.0 - reflectance) * base
+    }
+
+    /// Emission of the base through one pass of the coat. Its Fresnel transmittance is left
+    /// out, since the direction the emission is seen from is not known.
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        let base = self.base.emitted(hit_record);
+        if hit_record.front_face {
+            self.tint * base
+        } else {
+            base
+        }
+    }
+
+    /// Ignores `roughness`.
+    fn specular(&self, ray: &Ray, hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        let base = self.base.specular(ray, hit_record);
+        if !hit_record.front_face || base.is_empty() {
+            return base;
+        }
+        let unit_direction = unit_vector(ray.direction);
+        let cos_theta = dot_product(-unit_direction, hit_record.normal).clamp(0.0, 1.0);
+        let reflectance = fresnel_dielectric(cos_theta, self.ir);
+        let tint = (1.0 - reflectance) * (self.tint * self.tint);
+        let mut rays = vec![(
+            reflectance * WHITE,
+            hit_record.spawn_ray(ray, reflect(unit_direction, hit_record.normal)),
+        )];
+        rays.extend(
+            base.into_iter()
+                .map(|(attenuation, ray)| (tint * attenuation, ray)),
+        );
+        rays
+    }
+}
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+const WHITE: Color = Color {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+};
+
+/// Makes back faces behave like front faces, for open or single-sided surfaces.
+pub struct TwoSided {
+    pub material: SharedMaterial,
+}
+
+impl Material for TwoSided {
+    fn albedo(&self, hit_record: &HitRecord) -> Color {
+        self.material.albedo(hit_record)
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        let mut front = hit_record.clone();
+        front.front_face = true;
+        self.material.scatter(ray, &front)
+    }
+
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        let mut front = hit_record.clone();
+        front.front_face = true;
+        self.material.eval(&front, wo, wi)
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Float {
+        let mut front = hit_record.clone();
+        front.front_face = true;
+        self.material.pdf(&front, wo, wi)
+    }
+
+    fn specular(&self, ray: &Ray, hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        let mut front = hit_record.clone();
+        front.front_face = true;
+        self.material.specular(ray, &front)
+    }
+
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        let mut front = hit_record.clone();
+        front.front_face = true;
+        self.material.emitted(&front)
+    }
+}
@@ -0,0 +1,451 @@
+use crate::{
+    float::{consts::PI, Float},
+    helpers::random_float,
+    hittable::HitRecord,
+    ray::Ray,
+    vec3::{
+        cross_product, dot_product, orthonormal_basis, reflect, refract, unit_vector, Color, Vec3,
+    },
+};
+
+use super::{Absorption, Lobe, Material};
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+/// Anisotropic GGX (Trowbridge-Reitz) distribution of microfacet normals, expressed in a local
+/// frame where the macro surface normal is `+z`.
+#[derive(Copy, Clone)]
+pub struct Ggx {
+    pub alpha_x: Float,
+    pub alpha_y: Float,
+}
+
+impl Ggx {
+    /// Maps perceptual roughness along the two tangents to GGX alphas.
+    pub fn from_roughness(roughness_u: Float, roughness_v: Float) -> Ggx {
+        Ggx {
+            alpha_x: (roughness_u * roughness_u).max(1e-4),
+            alpha_y: (roughness_v * roughness_v).max(1e-4),
+        }
+    }
+
+    fn lambda(&self, w: Vec3) -> Float {
+        let x = self.alpha_x * w.x;
+        let y = self.alpha_y * w.y;
+        let tan2_alpha2 = (x * x + y * y) / (w.z * w.z);
+        (-1.0 + (1.0 + tan2_alpha2).sqrt()) / 2.0
+    }
+
+    /// Smith masking of a single direction, from either side of the surface.
+    pub fn g1(&self, w: Vec3) -> Float {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated Smith masking-shadowing.
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> Float {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of microfacet normals, per unit of projected area.
+    pub fn d(&self, h: Vec3) -> Float {
+        let x = h.x / self.alpha_x;
+        let y = h.y / self.alpha_y;
+        let t = x * x + y * y + h.z * h.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * t * t)
+    }
+
+    /// Reflection off the microfacets from `wo` to `wi`, both in the upper hemisphere: the half
+    /// vector, the BRDF times the cosine of `wi` without the Fresnel term, and the density with
+    /// which reflecting a normal from `sample_visible_normal` picks `wi`.
+    pub fn reflection(&self, wo: Vec3, wi: Vec3) -> Option<(Vec3, Float, Float)> {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return None;
+        }
+        let h = unit_vector(wo + wi);
+        let d = self.d(h);
+        Some((
+            h,
+            d * self.g(wo, wi) / (4.0 * wo.z),
+            d * self.g1(wo) / (4.0 * wo.z),
+        ))
+    }
+
+    /// Refraction through the microfacets from `wo` above the surface to `wi` below it, where
+    /// `eta` is the index of refraction below over the one above, as for `reflection`. Like
+    /// `scatter`, it leaves out the scaling of radiance by `1 / eta²`.
+    pub fn transmission(&self, wo: Vec3, wi: Vec3, eta: Float) -> Option<(Vec3, Float, Float)> {
+        if wo.z <= 0.0 || wi.z >= 0.0 {
+            return None;
+        }
+        let h = wo + eta * wi;
+        if h.near_zero() {
+            return None;
+        }
+        let h = if h.z < 0.0 {
+            -unit_vector(h)
+        } else {
+            unit_vector(h)
+        };
+        let cos_theta_o = dot_product(wo, h);
+        let cos_theta_i = dot_product(wi, h);
+        if cos_theta_o <= 0.0 || cos_theta_i >= 0.0 {
+            return None;
+        }
+        let denominator = cos_theta_i + cos_theta_o / eta;
+        // Density of visible normals times the change of variables from `h` to `wi`.
+        let density = self.d(h) * cos_theta_o / wo.z * -cos_theta_i / (denominator * denominator);
+        Some((h, self.g(wo, wi) * density, self.g1(wo) * density))
+    }
+
+    /// Samples a microfacet normal visible from `wo`, which must lie in the upper hemisphere
+    /// (Heitz 2018).
+    pub fn sample_visible_normal(&self, wo: Vec3) -> Vec3 {
+        let vh = unit_vector(Vec3 {
+            x: self.alpha_x * wo.x,
+            y: self.alpha_y * wo.y,
+            z: wo.z,
+        });
+        let len_squared = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if len_squared > 0.0 {
+            Vec3 {
+                x: -vh.y,
+                y: vh.x,
+                z: 0.0,
+            } / len_squared.sqrt()
+        } else {
+            Vec3 {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            }
+        };
+        let t2 = cross_product(vh, t1);
+
+        let r = random_float().sqrt();
+        let phi = 2.0 * PI * random_float();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+        unit_vector(Vec3 {
+            x: self.alpha_x * nh.x,
+            y: self.alpha_y * nh.y,
+            z: nh.z.max(1e-6),
+        })
+    }
+}
+
+/// Local shading frame built around the (incoming-side) normal of a hit.
+pub struct Frame {
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    pub normal: Vec3,
+}
+
+impl Frame {
+    /// Aligns the tangent with `dpdu` where the primitive provides one, so anisotropic
+    /// roughness follows the surface parameterization.
+    pub fn new(hit_record: &HitRecord) -> Frame {
+        let normal = hit_record.normal;
+        let tangent = hit_record.dpdu - dot_product(hit_record.dpdu, normal) * normal;
+        let (tangent, bitangent) = if tangent.len_squared() > 1e-12 {
+            let tangent = unit_vector(tangent);
+            (tangent, cross_product(normal, tangent))
+        } else {
+            orthonormal_basis(normal)
+        };
+        Frame {
+            tangent,
+            bitangent,
+            normal,
+        }
+    }
+
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3 {
+            x: dot_product(v, self.tangent),
+            y: dot_product(v, self.bitangent),
+            z: dot_product(v, self.normal),
+        }
+    }
+
+    pub fn to_world(&self, v: Vec3) -> Vec3 {
+        v.x * self.tangent + v.y * self.bitangent + v.z * self.normal
+    }
+}
+
+/// Fresnel reflectance of an interface with a complex index of refraction `eta + ik`,
+/// evaluated per color channel.
+pub fn fresnel_conductor(cos_theta: Float, eta: Color, k: Color) -> Color {
+    let channel = |eta: Float, k: Float| {
+        let cos2 = cos_theta * cos_theta;
+        let sin2 = 1.0 - cos2;
+        let eta2 = eta * eta;
+        let k2 = k * k;
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_theta * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        0.5 * (rp + rs)
+    };
+    Color {
+        x: channel(eta.x, k.x),
+        y: channel(eta.y, k.y),
+        z: channel(eta.z, k.z),
+    }
+}
+
+/// Unpolarized Fresnel reflectance of a dielectric interface, where `eta` is the ratio of the
+/// index of refraction on the transmitted side to the one on the incident side.
+pub fn fresnel_dielectric(cos_theta_i: Float, eta: Float) -> Float {
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Rough metal with GGX microfacets and complex-IOR Fresnel.
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub distribution: Ggx,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness_u: Float, roughness_v: Float) -> Conductor {
+        Conductor {
+            eta,
+            k,
+            distribution: Ggx::from_roughness(roughness_u, roughness_v),
+        }
+    }
+
+    pub fn gold(roughness: Float) -> Conductor {
+        Conductor::preset((0.143, 0.374, 1.442), (3.983, 2.385, 1.603), roughness)
+    }
+
+    pub fn copper(roughness: Float) -> Conductor {
+        Conductor::preset((0.200, 0.924, 1.102), (3.912, 2.452, 2.142), roughness)
+    }
+
+    pub fn aluminium(roughness: Float) -> Conductor {
+        Conductor::preset((1.657, 0.880, 0.521), (9.224, 6.270, 4.837), roughness)
+    }
+
+    pub fn silver(roughness: Float) -> Conductor {
+        Conductor::preset((0.155, 0.117, 0.138), (4.828, 3.122, 2.147), roughness)
+    }
+
+    fn preset(eta: (Float, Float, Float), k: (Float, Float, Float), roughness: Float) -> Conductor {
+        Conductor::new(
+            Color {
+                x: eta.0,
+                y: eta.1,
+                z: eta.2,
+            },
+            Color {
+                x: k.0,
+                y: k.1,
+                z: k.2,
+            },
+            roughness,
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn albedo(&self, _hit_record: &HitRecord) -> Color {
+        fresnel_conductor(1.0, self.eta, self.k)
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(-unit_vector(ray.direction));
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let h = self.distribution.sample_visible_normal(wo);
+        let wi = 2.0 * dot_product(wo, h) * h - wo;
+        if wi.z <= 0.0 {
+            return None;
+        }
+        let fresnel = fresnel_conductor(dot_product(wo, h), self.eta, self.k);
+        let weight = self.distribution.g(wo, wi) / self.distribution.g1(wo);
+        Some((
+            weight * fresnel,
+            hit_record.spawn_ray(ray, frame.to_world(wi)),
+            Lobe::Specular,
+        ))
+    }
+
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(wo);
+        Some(match self.distribution.reflection(wo, frame.to_local(wi)) {
+            Some((h, value, _)) => value * fresnel_conductor(dot_product(wo, h), self.eta, self.k),
+            None => BLACK,
+        })
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Float {
+        let frame = Frame::new(hit_record);
+        self.distribution
+            .reflection(frame.to_local(wo), frame.to_local(wi))
+            .map_or(0.0, |(_, _, pdf)| pdf)
+    }
+
+    /// Mirror reflection off the macro surface, ignoring roughness.
+    fn specular(&self, ray: &Ray, hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        let unit_direction = unit_vector(ray.direction);
+        let cos_theta = dot_product(-unit_direction, hit_record.normal).clamp(0.0, 1.0);
+        vec![(
+            fresnel_conductor(cos_theta, self.eta, self.k),
+            hit_record.spawn_ray(ray, reflect(unit_direction, hit_record.normal)),
+        )]
+    }
+}
+
+/// Frosted glass: a dielectric interface with GGX microfacets that both reflects and refracts.
+pub struct RoughDielectric {
+    pub ir: Float,
+    pub distribution: Ggx,
+    pub absorption: Option<Absorption>,
+}
+
+impl RoughDielectric {
+    pub fn new(ir: Float, roughness_u: Float, roughness_v: Float) -> RoughDielectric {
+        RoughDielectric {
+            ir,
+            distribution: Ggx::from_roughness(roughness_u, roughness_v),
+            absorption: None,
+        }
+    }
+
+    fn eta(&self, hit_record: &HitRecord) -> Float {
+        if hit_record.front_face {
+            self.ir
+        } else {
+            1.0 / self.ir
+        }
+    }
+
+    /// Value of `eval`, without the color, and of `pdf`.
+    fn lobes(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> (Float, Float) {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        let eta = self.eta(hit_record);
+        if let Some((h, value, pdf)) = self.distribution.reflection(wo, wi) {
+            let fresnel = fresnel_dielectric(dot_product(wo, h), eta);
+            return (fresnel * value, fresnel * pdf);
+        }
+        if let Some((h, value, pdf)) = self.distribution.transmission(wo, wi, eta) {
+            let fresnel = fresnel_dielectric(dot_product(wo, h), eta);
+            return ((1.0 - fresnel) * value, (1.0 - fresnel) * pdf);
+        }
+        (0.0, 0.0)
+    }
+
+    /// Whether `eval` applies at the hit. Inside absorbing glass the weight of `scatter` depends
+    /// on the length of the incoming ray, which `eval` does not see, so there the material can
+    /// only be sampled.
+    fn can_eval(&self, hit_record: &HitRecord) -> bool {
+        self.absorption.is_none() || hit_record.front_face
+    }
+}
+
+impl Material for RoughDielectric {
+    fn albedo(&self, _hit_record: &HitRecord) -> Color {
+        Color {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        }
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(-unit_vector(ray.direction));
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let eta = self.eta(hit_record);
+        let h = self.distribution.sample_visible_normal(wo);
+        let cos_theta_o = dot_product(wo, h);
+        let fresnel = fresnel_dielectric(cos_theta_o, eta);
+
+        let (wi, lobe) = if random_float() < fresnel {
+            let wi = 2.0 * cos_theta_o * h - wo;
+            if wi.z <= 0.0 {
+                return None;
+            }
+            (wi, Lobe::Specular)
+        } else {
+            let cos_theta_t = (1.0 - (1.0 - cos_theta_o * cos_theta_o) / (eta * eta)).sqrt();
+            let wi = -wo / eta + (cos_theta_o / eta - cos_theta_t) * h;
+            if wi.z >= 0.0 {
+                return None;
+            }
+            (wi, Lobe::Transmission { eta: 1.0 / eta })
+        };
+        let weight = self.distribution.g(wo, wi) / self.distribution.g1(wo);
+        Some((
+            weight * Absorption::along(self.absorption, ray, hit_record),
+            hit_record.spawn_ray(ray, frame.to_world(wi)),
+            lobe,
+        ))
+    }
+
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        if !self.can_eval(hit_record) {
+            return None;
+        }
+        let (value, _) = self.lobes(hit_record, wo, wi);
+        Some(Color {
+            x: value,
+            y: value,
+            z: value,
+        })
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Float {
+        if !self.can_eval(hit_record) {
+            return 0.0;
+        }
+        self.lobes(hit_record, wo, wi).1
+    }
+
+    /// Reflection and refraction through the macro surface, ignoring roughness.
+    fn specular(&self, ray: &Ray, hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        let attenuation = Absorption::along(self.absorption, ray, hit_record);
+        let eta = self.eta(hit_record);
+        let unit_direction = unit_vector(ray.direction);
+        let cos_theta = dot_product(-unit_direction, hit_record.normal).clamp(0.0, 1.0);
+        let fresnel = fresnel_dielectric(cos_theta, eta);
+        let mut rays = vec![(
+            fresnel * attenuation,
+            hit_record.spawn_ray(ray, reflect(unit_direction, hit_record.normal)),
+        )];
+        if fresnel < 1.0 {
+            rays.push((
+                (1.0 - fresnel) * attenuation,
+                hit_record.spawn_ray(ray, refract(unit_direction, hit_record.normal, 1.0 / eta)),
+            ));
+        }
+        rays
+    }
+}
@@ -0,0 +1,364 @@
+use std::sync::Arc;
+
+use crate::{
+    float::{consts::PI, Float},
+    helpers::{random_float, random_in_unit_sphere},
+    hittable::HitRecord,
+    ray::Ray,
+    vec3::{dot_product, reflect, refract, unit_vector, Color, Vec3},
+};
+
+pub use combinators::{Coated, Mix, MixWeight, TwoSided};
+pub use microfacet::{Conductor, RoughDielectric};
+pub use normal_mapped::{NormalMap, NormalMapped};
+pub use principled::Principled;
+
+mod combinators;
+mod microfacet;
+mod normal_mapped;
+mod principled;
+
+/// Kind of scattering event, used to limit the number of bounces of each kind along a path.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Lobe {
+    Diffuse,
+    /// Mirror-like and glossy reflection.
+    Specular,
+    /// Refraction through an interface, with `eta` the index of refraction on the side the ray
+    /// arrives from over the one on the side it leaves to.
+    Transmission {
+        eta: Float,
+    },
+    /// Scattering inside a medium.
+    Volume,
+}
+
+/// Material shared by the objects made of it.
+pub type SharedMaterial = Arc<Box<dyn Material + Send + Sync>>;
+
+pub trait Material {
+    fn scatter(&self, _ray: &Ray, _hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        None
+    }
+
+    fn albedo(&self, _hit_record: &HitRecord) -> Color {
+        Color {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    /// Radiance emitted from the hit point back along the incoming ray.
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        Color {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    /// BSDF times the cosine of `wi` for light arriving from `wi` and leaving towards `wo`, both
+    /// unit vectors pointing away from the surface. `None` for materials that can only be
+    /// sampled through `scatter`, which integrators treat like perfectly specular ones.
+    fn eval(&self, _hit_record: &HitRecord, _wo: Vec3, _wi: Vec3) -> Option<Color> {
+        None
+    }
+
+    /// Solid angle density with which `scatter` picks `wi` when seen from `wo`, for materials
+    /// that implement `eval`.
+    fn pdf(&self, _hit_record: &HitRecord, _wo: Vec3, _wi: Vec3) -> Float {
+        0.0
+    }
+
+    /// Ideal mirror and refraction rays leaving the hit point, with their weights, for
+    /// renderers that follow specular light deterministically instead of sampling `scatter`.
+    /// Empty for materials they should shade as diffuse.
+    fn specular(&self, _ray: &Ray, _hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        vec![]
+    }
+}
+
+pub struct Lambertian {
+    pub albedo: Color,
+}
+
+impl Material for Lambertian {
+    fn albedo(&self, _hit_record: &HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        let mut scatter_direction = hit_record.normal + unit_vector(random_in_unit_sphere());
+        if scatter_direction.near_zero() {
+            scatter_direction = hit_record.normal;
+        }
+        Some((
+            self.albedo,
+            hit_record.spawn_ray(ray, scatter_direction),
+            Lobe::Diffuse,
+        ))
+    }
+
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        let cos_theta = dot_product(hit_record.normal, wi);
+        if cos_theta <= 0.0 || dot_product(hit_record.normal, wo) <= 0.0 {
+            return Some(Color {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            });
+        }
+        Some((cos_theta / PI) * self.albedo)
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, _wo: Vec3, wi: Vec3) -> Float {
+        dot_product(hit_record.normal, wi).max(0.0) / PI
+    }
+}
+
+/// Area light emitting `emit` uniformly from the front side of a surface. It does not reflect.
+pub struct DiffuseLight {
+    pub emit: Color,
+}
+
+impl Material for DiffuseLight {
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        if hit_record.front_face {
+            self.emit
+        } else {
+            Color {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            }
+        }
+    }
+}
+
+pub struct Metal {
+    pub albedo: Color,
+    pub fuzzines: Float,
+}
+
+impl Metal {
+    fn fuzz(&self) -> Float {
+        self.fuzzines.min(1.0)
+    }
+}
+
+impl Material for Metal {
+    fn albedo(&self, _hit_record: &HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        let reflected = reflect(unit_vector(ray.direction), hit_record.normal);
+        let fuzz = self.fuzz();
+        let scattered = hit_record.spawn_ray(ray, reflected + fuzz * random_in_unit_sphere());
+        if dot_product(scattered.direction, hit_record.normal) > 0.0 {
+            Some((self.albedo, scattered, Lobe::Specular))
+        } else {
+            None
+        }
+    }
+
+    /// `None` for a perfect mirror.
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        if self.fuzz() <= 0.0 {
+            return None;
+        }
+        Some(self.pdf(hit_record, wo, wi) * self.albedo)
+    }
+
+    /// Scattered directions point from the origin to uniform points of the ball of radius
+    /// `fuzz` around the mirrored direction, so the density of a direction is the volume of
+    /// the ball along it, `∫ t² dt` between the two distances where it crosses the sphere, over
+    /// the volume of the ball.
+    fn pdf(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Float {
+        let fuzz = self.fuzz();
+        if fuzz <= 0.0 || dot_product(wi, hit_record.normal) <= 0.0 {
+            return 0.0;
+        }
+        let cos_alpha = dot_product(reflect(-wo, hit_record.normal), wi);
+        let discriminant = cos_alpha * cos_alpha - 1.0 + fuzz * fuzz;
+        if cos_alpha <= 0.0 || discriminant <= 0.0 {
+            return 0.0;
+        }
+        let near = (cos_alpha - discriminant.sqrt()).max(0.0);
+        let far = cos_alpha + discriminant.sqrt();
+        (far * far * far - near * near * near) / (4.0 * PI * fuzz * fuzz * fuzz)
+    }
+
+    /// Ignores `fuzzines`.
+    fn specular(&self, ray: &Ray, hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        vec![(
+            self.albedo,
+            hit_record.spawn_ray(ray, reflect(unit_vector(ray.direction), hit_record.normal)),
+        )]
+    }
+}
+
+/// Wavelength dependence of the index of refraction, with wavelengths in micrometers.
+#[derive(Copy, Clone)]
+pub enum Dispersion {
+    /// `n = a + b / λ²`
+    Cauchy { a: Float, b: Float },
+    /// `n² = 1 + Σ bᵢλ² / (λ² - cᵢ)`
+    Sellmeier { b: [Float; 3], c: [Float; 3] },
+}
+
+impl Dispersion {
+    /// Schott N-BK7 crown glass.
+    pub fn bk7() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [1.039_612, 0.231_792_35, 1.010_469_5],
+            c: [0.006_000_699, 0.020_017_914, 103.560_65],
+        }
+    }
+
+    /// Diamond, whose strong dispersion gives it its fire.
+    pub fn diamond() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030_625, 0.011_236, 0.0],
+        }
+    }
+
+    pub fn ir(&self, wavelength_nm: Float) -> Float {
+        let l = wavelength_nm / 1000.0;
+        let l2 = l * l;
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => (1.0
+                + b.iter()
+                    .zip(c.iter())
+                    .map(|(b, c)| b * l2 / (l2 - c))
+                    .sum::<Float>())
+            .sqrt(),
+        }
+    }
+}
+
+/// Beer-Lambert absorption of light travelling through a medium.
+#[derive(Copy, Clone)]
+pub struct Absorption {
+    /// Fraction of each channel transmitted after travelling `distance`.
+    pub color: Color,
+    pub distance: Float,
+}
+
+impl Absorption {
+    /// Attenuation after travelling `length` through the medium.
+    pub fn transmittance(&self, length: Float) -> Color {
+        let channel = |c: Float| c.max(Float::MIN_POSITIVE).powf(length / self.distance);
+        Color {
+            x: channel(self.color.x),
+            y: channel(self.color.y),
+            z: channel(self.color.z),
+        }
+    }
+
+    /// Attenuation of a ray that is leaving the medium at `hit_record`, having crossed it from
+    /// its previous intersection.
+    pub fn along(absorption: Option<Absorption>, ray: &Ray, hit_record: &HitRecord) -> Color {
+        match absorption {
+            Some(absorption) if !hit_record.front_face => {
+                absorption.transmittance(hit_record.t * ray.direction.len())
+            }
+            _ => Color {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+        }
+    }
+}
+
+pub struct Dielectric {
+    pub ir: Float,
+    /// Used instead of `ir` by rays that carry a wavelength.
+    pub dispersion: Option<Dispersion>,
+    /// Tints light by the distance it travels inside the object.
+    pub absorption: Option<Absorption>,
+}
+
+impl Dielectric {
+    fn ir_at(&self, wavelength: Option<Float>) -> Float {
+        match (self.dispersion, wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.ir(wavelength),
+            _ => self.ir,
+        }
+    }
+
+    fn reflectance(cosine: Float, ref_idx: Float) -> Float {
+        let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
+        r0 = r0 * r0;
+        return r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0);
+    }
+
+    /// Ratio of indices of refraction across the interface for `ray`, and the fraction of it
+    /// that is reflected, one for total internal reflection.
+    fn split(&self, ray: &Ray, hit_record: &HitRecord) -> (Float, Float) {
+        let ir = self.ir_at(ray.wavelength);
+        let refraction_ratio = if hit_record.front_face { 1.0 / ir } else { ir };
+        let cos_theta = dot_product(-unit_vector(ray.direction), hit_record.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        if refraction_ratio * sin_theta > 1.0 {
+            (refraction_ratio, 1.0)
+        } else {
+            (
+                refraction_ratio,
+                Dielectric::reflectance(cos_theta, refraction_ratio),
+            )
+        }
+    }
+}
+
+impl Material for Dielectric {
+    fn albedo(&self, _hit_record: &HitRecord) -> Color {
+        Color {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        }
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        let attenuation = Absorption::along(self.absorption, ray, hit_record);
+        let (refraction_ratio, reflectance) = self.split(ray, hit_record);
+        let unit_direction = unit_vector(ray.direction);
+        let (direction, lobe) = if reflectance > random_float() {
+            (reflect(unit_direction, hit_record.normal), Lobe::Specular)
+        } else {
+            (
+                refract(unit_direction, hit_record.normal, refraction_ratio),
+                Lobe::Transmission {
+                    eta: refraction_ratio,
+                },
+            )
+        };
+
+        Some((attenuation, hit_record.spawn_ray(ray, direction), lobe))
+    }
+
+    fn specular(&self, ray: &Ray, hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        let attenuation = Absorption::along(self.absorption, ray, hit_record);
+        let (refraction_ratio, reflectance) = self.split(ray, hit_record);
+        let unit_direction = unit_vector(ray.direction);
+        let mut rays = vec![(
+            reflectance * attenuation,
+            hit_record.spawn_ray(ray, reflect(unit_direction, hit_record.normal)),
+        )];
+        if reflectance < 1.0 {
+            rays.push((
+                (1.0 - reflectance) * attenuation,
+                hit_record.spawn_ray(
+                    ray,
+                    refract(unit_direction, hit_record.normal, refraction_ratio),
+                ),
+            ));
+        }
+        rays
+    }
+}
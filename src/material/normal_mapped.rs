@@ -0,0 +1,102 @@
+use crate::{
+    float::Float,
+    hittable::HitRecord,
+    ray::Ray,
+    texture::SharedTexture,
+    vec3::{cross_product, dot_product, unit_vector, Color, Vec3},
+};
+
+use super::{microfacet::Frame, Lobe, Material, SharedMaterial};
+
+pub enum NormalMap {
+    /// Tangent-space normals encoded as `0.5 * (n + 1)`, with `+z` along the surface normal
+    /// and `+x` along `dpdu`.
+    TangentSpace(SharedTexture),
+    /// Height field given by the luminance of the texture, scaled by `scale` world units.
+    Bump { height: SharedTexture, scale: Float },
+}
+
+/// Wraps any material and replaces the normal it scatters around with one perturbed by `map`.
+pub struct NormalMapped {
+    pub material: SharedMaterial,
+    pub map: NormalMap,
+}
+
+fn luminance(c: Color) -> Float {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+impl NormalMapped {
+    fn shading_normal(&self, hit_record: &HitRecord) -> Vec3 {
+        let n = hit_record.normal;
+        let perturbed = match &self.map {
+            NormalMap::TangentSpace(texture) => {
+                let c = texture.value(hit_record);
+                let frame = Frame::new(hit_record);
+                frame.to_world(Vec3 {
+                    x: 2.0 * c.x - 1.0,
+                    y: 2.0 * c.y - 1.0,
+                    z: 2.0 * c.z - 1.0,
+                })
+            }
+            NormalMap::Bump { height, scale } => {
+                // Finite differences over roughly a hundredth of a world unit along each
+                // tangent, following the displaced surface p + h(u, v) * n.
+                let height_at = |du: Float, dv: Float| {
+                    let mut shifted = hit_record.clone();
+                    shifted.u += du;
+                    shifted.v += dv;
+                    shifted.p = shifted.p + du * hit_record.dpdu + dv * hit_record.dpdv;
+                    scale * luminance(height.value(&shifted))
+                };
+                let du = 0.01 / hit_record.dpdu.len().max(1e-6);
+                let dv = 0.01 / hit_record.dpdv.len().max(1e-6);
+                let h = height_at(0.0, 0.0);
+                let dhdu = (height_at(du, 0.0) - h) / du;
+                let dhdv = (height_at(0.0, dv) - h) / dv;
+                let dpdu = hit_record.dpdu + dhdu * n;
+                let dpdv = hit_record.dpdv + dhdv * n;
+                cross_product(dpdu, dpdv)
+            }
+        };
+        if perturbed.near_zero() {
+            return n;
+        }
+        let perturbed = unit_vector(perturbed);
+        if dot_product(perturbed, n) < 0.0 {
+            -perturbed
+        } else {
+            perturbed
+        }
+    }
+}
+
+impl Material for NormalMapped {
+    fn albedo(&self, hit_record: &HitRecord) -> Color {
+        self.material.albedo(hit_record)
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        let mut shading = hit_record.clone();
+        shading.normal = self.shading_normal(hit_record);
+        self.material.scatter(ray, &shading)
+    }
+
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        let mut shading = hit_record.clone();
+        shading.normal = self.shading_normal(hit_record);
+        self.material.eval(&shading, wo, wi)
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Float {
+        let mut shading = hit_record.clone();
+        shading.normal = self.shading_normal(hit_record);
+        self.material.pdf(&shading, wo, wi)
+    }
+
+    fn specular(&self, ray: &Ray, hit_record: &HitRecord) -> Vec<(Color, Ray)> {
+        let mut shading = hit_record.clone();
+        shading.normal = self.shading_normal(hit_record);
+        self.material.specular(ray, &shading)
+    }
+}
@@ -0,0 +1,373 @@
+use crate::{
+    float::{consts::PI, Float},
+    helpers::{random_float, random_in_unit_sphere},
+    hittable::HitRecord,
+    ray::Ray,
+    vec3::{dot_product, unit_vector, Color, Vec3},
+};
+
+use super::{
+    microfacet::{fresnel_dielectric, Frame, Ggx},
+    Lobe, Material,
+};
+
+/// Layered "uber" material in the spirit of the Disney principled BSDF. A clearcoat sits on top
+/// of a blend between a metallic specular lobe and a dielectric base, which in turn splits
+/// between specular reflection, rough transmission and a diffuse lobe. Each scatter event picks
+/// one lobe with probability equal to its share of the energy.
+pub struct Principled {
+    pub base_color: Color,
+    pub metallic: Float,
+    pub roughness: Float,
+    /// Stretches the specular highlights along the surface tangent, from 0 to 1.
+    pub anisotropic: Float,
+    /// Scales the dielectric reflectance; 0.5 gives the Fresnel of `ior`.
+    pub specular: Float,
+    pub ior: Float,
+    pub transmission: Float,
+    pub clearcoat: Float,
+    pub clearcoat_roughness: Float,
+    /// Retro-reflective rim on the diffuse lobe, for cloth, from 0 to 1.
+    pub sheen: Float,
+    /// Blends the sheen color from white to the hue of `base_color`.
+    pub sheen_tint: Float,
+    /// Blends the diffuse color towards `subsurface_color` to mimic light scattered under the
+    /// surface.
+    pub subsurface: Float,
+    pub subsurface_color: Color,
+}
+
+impl Default for Principled {
+    fn default() -> Principled {
+        Principled {
+            base_color: Color {
+                x: 0.8,
+                y: 0.8,
+                z: 0.8,
+            },
+            metallic: 0.0,
+            roughness: 0.5,
+            anisotropic: 0.0,
+            specular: 0.5,
+            ior: 1.5,
+            transmission: 0.0,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            subsurface: 0.0,
+            subsurface_color: Color {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+        }
+    }
+}
+
+impl Principled {
+    /// Builds the material from the glTF 2.0 metallic-roughness model, with the
+    /// `KHR_materials_transmission`, `KHR_materials_ior` and `KHR_materials_clearcoat`
+    /// extensions. `base_color_factor` is linear, as in glTF.
+    pub fn from_gltf(
+        base_color_factor: Color,
+        metallic_factor: Float,
+        roughness_factor: Float,
+        transmission_factor: Float,
+        ior: Float,
+        clearcoat: (Float, Float),
+    ) -> Principled {
+        Principled {
+            base_color: base_color_factor,
+            metallic: metallic_factor,
+            roughness: roughness_factor,
+            transmission: transmission_factor,
+            ior,
+            clearcoat: clearcoat.0,
+            clearcoat_roughness: clearcoat.1,
+            ..Principled::default()
+        }
+    }
+
+    fn distribution(&self) -> Ggx {
+        let aspect = (1.0 - 0.9 * self.anisotropic).sqrt();
+        Ggx::from_roughness(
+            self.roughness / aspect.sqrt(),
+            self.roughness * aspect.sqrt(),
+        )
+    }
+
+    fn coat(&self) -> Ggx {
+        Ggx::from_roughness(self.clearcoat_roughness, self.clearcoat_roughness)
+    }
+
+    fn eta(&self, front_face: bool) -> Float {
+        if front_face {
+            self.ior
+        } else {
+            1.0 / self.ior
+        }
+    }
+
+    /// Dielectric reflectance at `cos_theta` to a normal, scaled by `specular`.
+    fn specular_reflectance(&self, cos_theta: Float, eta: Float) -> Float {
+        (2.0 * self.specular * fresnel_dielectric(cos_theta, eta)).min(1.0)
+    }
+
+    /// Color of the light refracted through the surface, so that light passing through an
+    /// object takes the base color.
+    fn transmission_tint(&self) -> Color {
+        Color {
+            x: self.base_color.x.sqrt(),
+            y: self.base_color.y.sqrt(),
+            z: self.base_color.z.sqrt(),
+        }
+    }
+
+    /// Color of the diffuse lobe for light arriving from `wi` and leaving towards `wo`. The
+    /// sheen takes its share from the diffuse color rather than adding to it, and its tint
+    /// keeps the hue of `base_color` at full brightness, so the lobe never reflects more light
+    /// than it receives.
+    fn diffuse_color(&self, wo: Vec3, wi: Vec3) -> Color {
+        let diffuse = lerp(self.base_color, self.subsurface_color, self.subsurface);
+        let brightest = self
+            .base_color
+            .x
+            .max(self.base_color.y)
+            .max(self.base_color.z);
+        let tint = if brightest > 0.0 {
+            self.base_color / brightest
+        } else {
+            WHITE
+        };
+        let sheen = lerp(WHITE, tint, self.sheen_tint);
+        let half = unit_vector(wo + wi);
+        let share = (self.sheen * schlick_weight(dot_product(wi, half))).clamp(0.0, 1.0);
+        lerp(diffuse, sheen, share)
+    }
+
+    /// Chances of each lobe being picked for light leaving towards `wo`, in the local frame.
+    fn lobes(&self, wo: Vec3, front_face: bool) -> Lobes {
+        // The clearcoat is a thin, colorless dielectric layer with IOR 1.5 on the outside only.
+        let clearcoat = if front_face {
+            self.clearcoat * fresnel_dielectric(wo.z, 1.5)
+        } else {
+            0.0
+        };
+        let metallic = (1.0 - clearcoat) * self.metallic;
+        let dielectric = (1.0 - clearcoat) * (1.0 - self.metallic);
+        let specular = dielectric * self.specular_reflectance(wo.z, self.eta(front_face));
+        let transmission = (dielectric - specular) * self.transmission;
+        // Only transmission lobes can leave the inside of a closed object.
+        let diffuse = if front_face {
+            dielectric - specular - transmission
+        } else {
+            0.0
+        };
+        Lobes {
+            clearcoat,
+            metallic,
+            dielectric,
+            specular,
+            transmission,
+            diffuse,
+        }
+    }
+
+    /// Values of `eval` and `pdf` for directions in the local frame.
+    fn eval_local(&self, wo: Vec3, wi: Vec3, front_face: bool) -> (Color, Float) {
+        let mut f = BLACK;
+        let mut pdf = 0.0;
+        if wo.z <= 0.0 {
+            return (f, pdf);
+        }
+        let lobes = self.lobes(wo, front_face);
+        let eta = self.eta(front_face);
+        if lobes.clearcoat > 0.0 {
+            if let Some((h, value, density)) = self.coat().reflection(wo, wi) {
+                let fresnel = self.clearcoat * fresnel_dielectric(dot_product(wo, h), 1.5);
+                f += &(fresnel * value * WHITE);
+                pdf += lobes.clearcoat * density;
+            }
+        }
+        let distribution = self.distribution();
+        if let Some((h, value, density)) = distribution.reflection(wo, wi) {
+            let cos_theta_o = dot_product(wo, h);
+            let metallic = lerp(self.base_color, WHITE, schlick_weight(cos_theta_o));
+            let specular = self.specular_reflectance(cos_theta_o, eta);
+            f += &(lobes.metallic * value * metallic + lobes.dielectric * specular * value * WHITE);
+            pdf += (lobes.metallic + lobes.specular) * density;
+        }
+        if lobes.transmission > 0.0 {
+            if let Some((_, value, density)) = distribution.transmission(wo, wi, eta) {
+                f += &(lobes.transmission * value * self.transmission_tint());
+                pdf += lobes.transmission * density;
+            }
+        }
+        if lobes.diffuse > 0.0 && wi.z > 0.0 {
+            f += &(lobes.diffuse * wi.z / PI * self.diffuse_color(wo, wi));
+            pdf += lobes.diffuse * wi.z / PI;
+        }
+        (f, pdf)
+    }
+}
+
+/// Chances of `scatter` picking each lobe, which follow the Fresnel reflectance of the macro
+/// surface. The same terms dim the light reaching the layers below, so the lobes weigh as
+/// much in `eval` as in `scatter`, while each lobe reflects with the Fresnel reflectance of its
+/// microfacets.
+struct Lobes {
+    clearcoat: Float,
+    metallic: Float,
+    /// Share of the light reaching the dielectric base, which `specular`, `transmission` and
+    /// `diffuse` split between them.
+    dielectric: Float,
+    specular: Float,
+    transmission: Float,
+    diffuse: Float,
+}
+
+fn schlick_weight(cos_theta: Float) -> Float {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+fn lerp(a: Color, b: Color, t: Float) -> Color {
+    (1.0 - t) * a + t * b
+}
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+const WHITE: Color = Color {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+};
+
+impl Material for Principled {
+    fn albedo(&self, _hit_record: &HitRecord) -> Color {
+        self.base_color
+    }
+
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray, Lobe)> {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(-unit_vector(ray.direction));
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let scattered = |wi: Vec3| hit_record.spawn_ray(ray, frame.to_world(wi));
+        let reflect = |wo: Vec3, h: Vec3| 2.0 * dot_product(wo, h) * h - wo;
+        let lobes = self.lobes(wo, hit_record.front_face);
+        let mut choice = random_float();
+
+        if choice < lobes.clearcoat {
+            let coat = self.coat();
+            let h = coat.sample_visible_normal(wo);
+            let wi = reflect(wo, h);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            let fresnel =
+                fresnel_dielectric(dot_product(wo, h), 1.5) / fresnel_dielectric(wo.z, 1.5);
+            let weight = fresnel * coat.g(wo, wi) / coat.g1(wo);
+            return Some((weight * WHITE, scattered(wi), Lobe::Specular));
+        }
+        choice -= lobes.clearcoat;
+
+        let distribution = self.distribution();
+        let h = distribution.sample_visible_normal(wo);
+        let cos_theta_o = dot_product(wo, h);
+
+        if choice < lobes.metallic {
+            let wi = reflect(wo, h);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            let fresnel = lerp(self.base_color, WHITE, schlick_weight(cos_theta_o));
+            let weight = distribution.g(wo, wi) / distribution.g1(wo);
+            return Some((weight * fresnel, scattered(wi), Lobe::Specular));
+        }
+        choice -= lobes.metallic;
+
+        let eta = self.eta(hit_record.front_face);
+        if choice < lobes.specular {
+            let wi = reflect(wo, h);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            let fresnel =
+                self.specular_reflectance(cos_theta_o, eta) / self.specular_reflectance(wo.z, eta);
+            let weight = fresnel * distribution.g(wo, wi) / distribution.g1(wo);
+            return Some((weight * WHITE, scattered(wi), Lobe::Specular));
+        }
+        choice -= lobes.specular;
+
+        if choice < lobes.transmission {
+            let sin2_theta_t = (1.0 - cos_theta_o * cos_theta_o) / (eta * eta);
+            if sin2_theta_t >= 1.0 {
+                return None;
+            }
+            let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+            let wi = -wo / eta + (cos_theta_o / eta - cos_theta_t) * h;
+            if wi.z >= 0.0 {
+                return None;
+            }
+            let weight = distribution.g(wo, wi) / distribution.g1(wo);
+            return Some((
+                weight * self.transmission_tint(),
+                scattered(wi),
+                Lobe::Transmission { eta: 1.0 / eta },
+            ));
+        }
+        choice -= lobes.transmission;
+
+        if choice >= lobes.diffuse {
+            return None;
+        }
+        let normal = Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        };
+        let mut direction = normal + unit_vector(random_in_unit_sphere());
+        if direction.near_zero() {
+            direction = normal;
+        }
+        direction = unit_vector(direction);
+        // The subsurface blend stands in for light scattered inside the object, so those bounces
+        // count against the volume depth.
+        let lobe = if random_float() < self.subsurface {
+            Lobe::Volume
+        } else {
+            Lobe::Diffuse
+        };
+        Some((
+            self.diffuse_color(wo, direction),
+            scattered(direction),
+            lobe,
+        ))
+    }
+
+    fn eval(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Option<Color> {
+        let frame = Frame::new(hit_record);
+        let (f, _) = self.eval_local(
+            frame.to_local(wo),
+            frame.to_local(wi),
+            hit_record.front_face,
+        );
+        Some(f)
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, wo: Vec3, wi: Vec3) -> Float {
+        let frame = Frame::new(hit_record);
+        let (_, pdf) = self.eval_local(
+            frame.to_local(wo),
+            frame.to_local(wi),
+            hit_record.front_face,
+        );
+        pdf
+    }
+}
@@ -0,0 +1,94 @@
+//! Minimal PNG writer for 8-bit RGB images. The pixel data is stored uncompressed inside the
+//! zlib stream, which every decoder reads and which needs no compression library.
+
+use std::{fs::File, io::Write};
+
+use crate::image::Image;
+
+/// PNG of `image`, encoded with the sRGB transfer curve for display.
+pub fn generate_png(image: &Image) -> Vec<u8> {
+    generate(image.width, image.height, &image.to_srgb8())
+}
+
+/// PNG of `image` holding colors already in the `[0, 1]` range, without a transfer curve.
+pub fn generate_png_linear(image: &Image) -> Vec<u8> {
+    generate(image.width, image.height, &image.to_linear8())
+}
+
+pub fn save_png(value: &[u8], path: &str) -> Result<(), std::io::Error> {
+    let mut file = File::create(path)?;
+    file.write_all(value)?;
+    Result::Ok(())
+}
+
+/// Largest payload of a stored deflate block.
+const MAX_STORED: usize = 65_535;
+
+fn generate(w: i32, h: i32, bytes: &[u8]) -> Vec<u8> {
+    // Every row starts with the byte choosing its filter, none here.
+    let row = 3 * w as usize;
+    let mut filtered = Vec::with_capacity((row + 1) * h as usize);
+    for pixels in bytes.chunks_exact(row) {
+        filtered.push(0);
+        filtered.extend_from_slice(pixels);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = filtered.chunks(MAX_STORED).collect();
+    for (i, block) in blocks.iter().enumerate() {
+        let last = i + 1 == blocks.len();
+        let length = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&length.to_le_bytes());
+        zlib.extend_from_slice(&(!length).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    if blocks.is_empty() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    zlib.extend_from_slice(&adler32(&filtered).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(w as u32).to_be_bytes());
+    header.extend_from_slice(&(h as u32).to_be_bytes());
+    // Bit depth 8, truecolor, default compression and filtering, no interlacing.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut result = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    push_chunk(&mut result, b"IHDR", &header);
+    push_chunk(&mut result, b"IDAT", &zlib);
+    push_chunk(&mut result, b"IEND", &[]);
+    result
+}
+
+fn push_chunk(result: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    result.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    result.extend_from_slice(kind);
+    result.extend_from_slice(data);
+    let crc = crc32(kind.iter().chain(data));
+    result.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    (b << 16) | a
+}
@@ -1,34 +1,126 @@
-use std::{fs::File, io::Write};
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Read, Write},
+};
 
-use crate::{helpers::clamp, vec3::Color};
+use crate::{
+    float::Float,
+    image::{srgb_to_linear, Image},
+    vec3::Color,
+};
 
-pub fn generate_ppm(w: i32, h: i32, vec: &Vec<Color>, samples_per_pixel: i32) -> String {
-    let scale = 1.0 / samples_per_pixel as f32;
+/// Plain PPM of `image`, encoded with the sRGB transfer curve for display.
+pub fn generate_ppm(image: &Image) -> String {
+    generate(image.width, image.height, &image.to_srgb8())
+}
+
+/// Plain PPM of `image` holding colors already in the `[0, 1]` range, without a transfer curve.
+pub fn generate_ppm_linear(image: &Image) -> String {
+    generate(image.width, image.height, &image.to_linear8())
+}
+
+fn generate(w: i32, h: i32, bytes: &[u8]) -> String {
     let mut result = String::from("P3\n");
     result.push_str(format!("{} {}\n", w, h).as_str());
     result.push_str("255\n");
-    for y in 0..h {
-        for x in 0..w {
-            let i = (w * y + x) as usize;
-            let r = (vec[i].x * scale).sqrt();
-            let g = (vec[i].y * scale).sqrt();
-            let b = (vec[i].z * scale).sqrt();
-            result.push_str(
-                format!(
-                    "{} {} {}\n",
-                    (256.00 * clamp(r, 0.0, 0.99)) as u8,
-                    (256.00 * clamp(g, 0.0, 0.99)) as u8,
-                    (256.00 * clamp(b, 0.0, 0.99)) as u8
-                )
-                .as_str(),
-            )
-        }
+    for pixel in bytes.chunks_exact(3) {
+        result.push_str(format!("{} {} {}\n", pixel[0], pixel[1], pixel[2]).as_str());
     }
     result
 }
 
-pub fn save_ppm(value: &str) -> Result<(), std::io::Error> {
-    let mut file = File::create("image.ppm")?;
+pub fn save_ppm(value: &str, path: &str) -> Result<(), std::io::Error> {
+    let mut file = File::create(path)?;
     file.write_all(value.as_bytes())?;
     Result::Ok(())
 }
+
+/// Reads an ASCII (`P3`) or binary (`P6`) PPM file into colors in the `[0, 1]` range, as stored
+/// without decoding any transfer curve.
+pub fn read_ppm(path: &str) -> Result<Image, Error> {
+    let mut bytes = vec![];
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+    let mut position = 0;
+    let mut header = vec![];
+    while header.len() < 4 {
+        while position < bytes.len() && bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if position < bytes.len() && bytes[position] == b'#' {
+            while position < bytes.len() && bytes[position] != b'\n' {
+                position += 1;
+            }
+            continue;
+        }
+        let start = position;
+        while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err(invalid("truncated PPM header"));
+        }
+        header.push(String::from_utf8_lossy(&bytes[start..position]).to_string());
+    }
+    let number = |value: &str| {
+        value
+            .parse::<i32>()
+            .map_err(|_| invalid("invalid number in PPM header"))
+    };
+    let (w, h, max_value) = (
+        number(&header[1])?,
+        number(&header[2])?,
+        number(&header[3])?,
+    );
+    if w <= 0 || h <= 0 {
+        return Err(invalid("PPM dimensions must be positive"));
+    }
+    if !(1..=65535).contains(&max_value) {
+        return Err(invalid("PPM maximum value must be between 1 and 65535"));
+    }
+    let count = w
+        .checked_mul(h)
+        .and_then(|pixels| pixels.checked_mul(3))
+        .ok_or_else(|| invalid("PPM dimensions are too large"))? as usize;
+    let binary = bytes.get(position + 1..).unwrap_or(&[]);
+
+    let values: Vec<Float> = match header[0].as_str() {
+        "P3" => String::from_utf8_lossy(&bytes[position..])
+            .split_ascii_whitespace()
+            .take(count)
+            .map(|v| number(v).map(|v| v as Float))
+            .collect::<Result<_, _>>()?,
+        "P6" if max_value < 256 => binary.iter().take(count).map(|v| *v as Float).collect(),
+        "P6" => binary
+            .chunks_exact(2)
+            .take(count)
+            .map(|v| u16::from_be_bytes([v[0], v[1]]) as Float)
+            .collect(),
+        _ => return Err(invalid("unsupported PPM format")),
+    };
+    if values.len() < count {
+        return Err(invalid("truncated PPM data"));
+    }
+    let scale = 1.0 / max_value as Float;
+    let pixels = values
+        .chunks_exact(3)
+        .map(|c| Color {
+            x: c[0] * scale,
+            y: c[1] * scale,
+            z: c[2] * scale,
+        })
+        .collect();
+    Ok(Image::from_pixels(w, h, pixels))
+}
+
+/// Reads a PPM file encoded for display, like those `generate_ppm` writes, back into linear
+/// colors.
+pub fn read_ppm_srgb(path: &str) -> Result<Image, Error> {
+    let image = read_ppm(path)?;
+    Ok(image.map(|c| Color {
+        x: srgb_to_linear(c.x),
+        y: srgb_to_linear(c.y),
+        z: srgb_to_linear(c.z),
+    }))
+}
@@ -1,50 +1,48 @@
 use crate::{
-    hittable::{hit, Hittable},
-    vec3::{unit_vector, Color, Point3, Vec3},
+    float::Float,
+    vec3::{dot_product, Point3, Vec3},
 };
 
+#[derive(Copy, Clone)]
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    /// Moment the ray was cast, in frames; animated objects are posed for this time.
+    pub time: Float,
+    /// Wavelength in nanometers carried by rays of the spectral integrator, `None` for RGB.
+    pub wavelength: Option<Float>,
 }
 
 impl Ray {
-    pub fn at(&self, t: f32) -> Point3 {
+    pub fn at(&self, t: Float) -> Point3 {
         self.origin + (t * self.direction)
     }
+}
 
-    pub fn color(&self, world: &[impl Hittable], depth: i32) -> Color {
-        if depth <= 0 {
-            return Color {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            };
-        }
-        if let Some(hit) = hit(world, self, 0.001, f32::MAX) {
-            let material = &hit.material.clone().unwrap();
-            if let Some(scatter) = (*material).scatter(self, &hit) {
-                return scatter.0 * scatter.1.color(world, depth - 1);
-            } else {
-                return Color {
-                    x: 0.0,
-                    y: 0.0,
-                    z: 0.0,
-                };
-            }
+/// Origin for a ray leaving the surface through `p` in direction `w`: `p` pushed along the
+/// geometric `normal`, to the side `w` points to, just out of the box `p_error` bounds its
+/// rounding error by. The surface then lies wholly behind the ray, which cannot hit it again
+/// right away however far the scene extends.
+pub fn offset_origin(p: Point3, p_error: Vec3, normal: Vec3, w: Vec3) -> Point3 {
+    let distance = dot_product(normal.abs(), p_error);
+    let offset = if dot_product(w, normal) < 0.0 {
+        -distance * normal
+    } else {
+        distance * normal
+    };
+    // Round away from `p`, so rounding the sum cannot move it back into the box.
+    let away = |p: Float, offset: Float| {
+        if offset > 0.0 {
+            (p + offset).next_up()
+        } else if offset < 0.0 {
+            (p + offset).next_down()
+        } else {
+            p
         }
-        let unit_direction = unit_vector(self.direction);
-        let t = 0.5 * (unit_direction.y + 1.0);
-        (1.0 - t)
-            * Color {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            }
-            + t * Color {
-                x: 0.5,
-                y: 0.7,
-                z: 1.0,
-            }
+    };
+    Point3 {
+        x: away(p.x, offset.x),
+        y: away(p.y, offset.y),
+        z: away(p.z, offset.z),
     }
 }
@@ -0,0 +1,278 @@
+use std::{error, sync::Arc, thread::available_parallelism};
+
+use crate::{
+    aov::{AovBuffers, AovSample},
+    camera::{
+        Aperture, ApertureMask, Camera, Equirectangular, Fisheye, Orthographic, SharedCamera,
+        Stereo,
+    },
+    float::Float,
+    helpers::{degrees_to_radians, random_float, seed_random},
+    image::Image,
+    integrator::{
+        Bidirectional, DebugIntegrator, PathTracer, PhotonMapper, PhotonSettings, SharedIntegrator,
+        Whitted,
+    },
+    scene::{Scene, View},
+    settings::{CameraKind, IntegratorKind, RenderSettings},
+    spectral::{sample_wavelength, to_rgb},
+    vec3::{Color, Vec3},
+};
+
+/// Rendered frame: the mean of the samples of each pixel and the first-hit AOVs.
+pub struct Frame {
+    pub image: Image,
+    pub aovs: AovBuffers,
+}
+
+/// Camera of the kind and lens chosen by `settings`, placed at `view`. Auto focus measures the
+/// distance to the objects of `scene` as they are at `time`.
+pub fn build_camera(
+    settings: &RenderSettings,
+    scene: &Scene,
+    view: View,
+    time: Float,
+) -> Result<SharedCamera, Box<dyn error::Error>> {
+    let View {
+        look_from,
+        look_at,
+        vfov,
+    } = view;
+    let aspect_ratio = settings.aspect_ratio;
+    let dist_to_focus = 10.0;
+    let aperture = 0.1;
+    let v_up = Vec3 {
+        x: 0.0,
+        y: 1.0,
+        z: 0.0,
+    };
+    let mut lens = settings.lens.clone();
+    if let Some(path) = &settings.aperture_mask {
+        lens.aperture = Aperture::Mask(Arc::new(ApertureMask::from_ppm(path)?));
+    }
+    let camera: SharedCamera = match settings.camera {
+        CameraKind::Perspective => {
+            let mut builder = Camera::builder()
+                .look_from(look_from)
+                .look_at(look_at)
+                .v_up(v_up)
+                .aspect_ratio(aspect_ratio)
+                .vertical_fov(vfov)
+                .aperture(aperture)
+                .focus_distance(dist_to_focus)
+                .lens(lens);
+            if let Some(focal_length) = settings.focal_length {
+                builder = builder.focal_length(focal_length);
+            }
+            if let Some((width, height)) = settings.sensor_size {
+                builder = builder.sensor_size(width, height);
+            }
+            if let Some(scale) = settings.scene_units_per_mm {
+                builder = builder.scene_units_per_mm(scale);
+            }
+            if let Some(f_number) = settings.f_number {
+                builder = builder.f_number(f_number);
+            }
+            if settings.auto_focus {
+                builder = builder.auto_focus(&scene.world, time);
+            }
+            Arc::new(builder.build()?)
+        }
+        CameraKind::Orthographic => Arc::new(Orthographic::new(
+            look_from,
+            look_at,
+            v_up,
+            2.0 * (degrees_to_radians(vfov) / 2.0).tan() * dist_to_focus,
+            aspect_ratio,
+        )),
+        CameraKind::Panorama => Arc::new(Equirectangular::new(look_from, look_at, v_up)),
+        CameraKind::Fisheye(mapping) => Arc::new(Fisheye::new(
+            look_from,
+            look_at,
+            v_up,
+            180.0,
+            aspect_ratio,
+            mapping,
+        )),
+        CameraKind::Stereo => Arc::new(Stereo::new(
+            look_from,
+            look_at,
+            v_up,
+            vfov,
+            aspect_ratio / 2.0,
+            aperture,
+            dist_to_focus,
+            0.5,
+        )),
+    };
+    Ok(camera)
+}
+
+/// Integrator chosen by `settings`, set up for the lights and background of `scene` as they are
+/// at `time`.
+pub fn build_integrator(settings: &RenderSettings, scene: &Scene, time: Float) -> SharedIntegrator {
+    let world = &scene.world;
+    let background = scene.background;
+    let sky_bounds = scene.sky_bounds;
+    let lights = &scene.lights;
+    match settings.integrator {
+        IntegratorKind::Path => Arc::new(PathTracer {
+            lights: lights.to_vec(),
+            depths: settings.depths,
+            clamp: settings.clamp,
+            background,
+        }),
+        IntegratorKind::Bidirectional => Arc::new(Bidirectional::new(
+            world,
+            lights.to_vec(),
+            settings.depths,
+            background,
+        )),
+        IntegratorKind::Photon => Arc::new(PhotonMapper::new(
+            world,
+            lights.to_vec(),
+            settings.depths,
+            background,
+            sky_bounds,
+            PhotonSettings {
+                passes: 1,
+                ..settings.photons
+            },
+            time,
+        )),
+        IntegratorKind::ProgressivePhoton => Arc::new(PhotonMapper::new(
+            world,
+            lights.to_vec(),
+            settings.depths,
+            background,
+            sky_bounds,
+            settings.photons,
+            time,
+        )),
+        IntegratorKind::Whitted => Arc::new(Whitted {
+            lights: lights.to_vec(),
+            background,
+            ambient: 0.1,
+            max_depth: settings.depths.total,
+        }),
+        IntegratorKind::Debug(view) => Arc::new(DebugIntegrator {
+            view,
+            settings: settings.debug,
+            depths: settings.depths,
+        }),
+    }
+}
+
+/// Renders `scene` from its own view with the camera and integrator chosen by `settings`.
+pub fn render(settings: &RenderSettings, scene: &Scene) -> Result<Frame, Box<dyn error::Error>> {
+    let camera = build_camera(settings, scene, scene.view, 0.0)?;
+    let integrator = build_integrator(settings, scene, 0.0);
+    render_frame(settings, scene, &camera, &integrator, 0.0)
+}
+
+/// Renders one frame of `scene` with every ray cast at `time`.
+pub fn render_frame(
+    settings: &RenderSettings,
+    scene: &Scene,
+    camera: &SharedCamera,
+    integrator: &SharedIntegrator,
+    time: Float,
+) -> Result<Frame, Box<dyn error::Error>> {
+    let world = &scene.world;
+    let image_width = settings.image_width;
+    let image_height = settings.image_height();
+    let samples_per_pixel = settings.samples_per_pixel;
+    let record_aovs = settings.aovs;
+    let spectral = settings.spectral;
+    let seed = settings.seed;
+
+    let threads = available_parallelism()?.get() as i32;
+    // Threads take every `threads`-th row, so the expensive parts of the image are shared out.
+    let handlers: Vec<_> = (0..threads)
+        .map(|t| {
+            let world_for_thread = Arc::clone(world);
+            let camera_for_thread = Arc::clone(camera);
+            let integrator_for_thread = Arc::clone(integrator);
+            std::thread::spawn(move || {
+                let rows: Vec<i32> = (t..image_height).step_by(threads as usize).collect();
+                let mut band = Image::new(image_width, rows.len() as i32);
+                let mut splats = vec![];
+                let mut aov_samples = vec![];
+                for (row, y) in rows.iter().copied().enumerate() {
+                    // Seeding each row rather than each thread keeps the image the same whatever
+                    // the number of threads.
+                    if let Some(seed) = seed {
+                        // `Float` is already 64 bits wide when built with the `f64` feature.
+                        #[allow(clippy::useless_conversion)]
+                        let time_bits = u64::from(time.to_bits());
+                        seed_random(seed ^ ((y as u64) << 32) ^ time_bits);
+                    }
+                    for x in 0..image_width {
+                        for s in 0..samples_per_pixel {
+                            let u = ((x as Float) + random_float()) / (image_width as Float);
+                            let v = 1.0 - ((y as Float) + random_float()) / (image_height as Float);
+                            let (mut r, weight) = camera_for_thread.get_sample(u, v);
+                            r.time = time;
+                            if record_aovs && s == 0 {
+                                let sample = world_for_thread
+                                    .hit(&r, 0.0, Float::MAX)
+                                    .map(|h| AovSample::from_hit(&r, &h));
+                                aov_samples.push((x, y, sample));
+                            }
+                            let mut sample_splats = vec![];
+                            let wavelength = if spectral {
+                                let (wavelength, pdf) = sample_wavelength();
+                                r.wavelength = Some(wavelength);
+                                Some((wavelength, pdf))
+                            } else {
+                                None
+                            };
+                            let l = integrator_for_thread.radiance(
+                                &r,
+                                &world_for_thread,
+                                camera_for_thread.as_ref(),
+                                &mut sample_splats,
+                            );
+                            let to_film = |l: Color| match wavelength {
+                                Some((wavelength, pdf)) => to_rgb(wavelength, l.x, pdf),
+                                None => l,
+                            };
+                            band.accumulate(x, row as i32, weight * to_film(l));
+                            for splat in sample_splats {
+                                let x =
+                                    ((splat.u * image_width as Float) as i32).min(image_width - 1);
+                                let y = (((1.0 - splat.v) * image_height as Float) as i32)
+                                    .min(image_height - 1);
+                                splats.push((x, y, to_film(splat.color)));
+                            }
+                        }
+                    }
+                }
+                (rows, band, splats, aov_samples)
+            })
+        })
+        .collect();
+
+    let mut image = Image::new(image_width, image_height);
+    let mut aovs = AovBuffers::new(image_width, image_height);
+    let mut splats = vec![];
+    for handler in handlers {
+        let (rows, band, band_splats, aov_samples) = handler.join().unwrap();
+        for (row, y) in rows.iter().enumerate() {
+            image.paste(0, *y, &band.crop(0, row as i32, image_width, 1));
+        }
+        splats.extend(band_splats);
+        for (x, y, sample) in aov_samples {
+            aovs.record(x, y, sample);
+        }
+    }
+    // Splats can land on rows of any thread, so they go in once every row is in place.
+    for (x, y, color) in splats {
+        image.accumulate(x, y, color);
+    }
+    let scale = 1.0 / samples_per_pixel as Float;
+    Ok(Frame {
+        image: image.map(|c| scale * c),
+        aovs,
+    })
+}
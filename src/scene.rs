@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use crate::{
+    animation::CameraAnimation,
+    float::Float,
+    hittable::{BoxedHittable, World},
+    integrator::{Background, BoundingSphere},
+    light::Light,
+    vec3::Point3,
+};
+
+/// Placement of a camera: where it looks from and at, and its vertical field of view in degrees.
+#[derive(Copy, Clone)]
+pub struct View {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub vfov: Float,
+}
+
+/// Everything that makes up a frame besides the camera: the objects, the light reaching them
+/// from the background and from lights without a surface, and the view it is meant to be seen
+/// from.
+pub struct Scene {
+    pub world: Arc<World>,
+    pub view: View,
+    pub background: Background,
+    /// Encloses the objects that can focus sky light into caustics, for the photon mapping
+    /// integrators. Without it only area lights cast caustics.
+    pub sky_bounds: Option<BoundingSphere>,
+    pub lights: Vec<Light>,
+    /// Keyframed camera of an image sequence. Without it every frame is seen from `view`.
+    pub camera_animation: Option<CameraAnimation>,
+}
+
+impl Scene {
+    /// Scene of `objects` under the sky, without lights of its own.
+    pub fn new(objects: Vec<BoxedHittable>, view: View) -> Scene {
+        Scene {
+            world: Arc::new(World::new(objects)),
+            view,
+            background: Background::Sky,
+            sky_bounds: None,
+            lights: vec![],
+            camera_animation: None,
+        }
+    }
+
+    /// Where the camera is at `time`.
+    pub fn view_at(&self, time: Float) -> View {
+        match &self.camera_animation {
+            Some(animation) => {
+                let (look_from, look_at, vfov) = animation.sample(time);
+                View {
+                    look_from,
+                    look_at,
+                    vfov,
+                }
+            }
+            None => self.view,
+        }
+    }
+}
@@ -0,0 +1,839 @@
+//! Built-in demo scenes selected with `--scene`.
+
+use std::{error, sync::Arc};
+
+use crate::{
+    animation::{CameraAnimation, Interpolation, Track, TrackError, Transform},
+    float::Float,
+    helpers::{degrees_to_radians, random_float, random_float_in_range, seed_random},
+    hittable::{AlphaMasked, AlphaMode, Animated, BoxedHittable, Quad, Sphere},
+    integrator::{Background, BoundingSphere},
+    light::Light,
+    material::{
+        Absorption, Coated, Conductor, Dielectric, DiffuseLight, Dispersion, Lambertian, Metal,
+        Mix, MixWeight, NormalMap, NormalMapped, Principled, RoughDielectric, SharedMaterial,
+        TwoSided,
+    },
+    scene::{Scene, View},
+    settings::{RenderSettings, SceneKind},
+    texture::{Checker, ImageTexture, SharedTexture},
+    vec3::{Color, Point3, Vec3},
+};
+
+/// Scene chosen by `settings`, with its lights replaced by those given on the command line. With
+/// a seed set, the random numbers of this thread are seeded first, so the random scene and
+/// whatever is drawn on this thread after it, like photon maps, repeat as well.
+pub fn from_settings(settings: &RenderSettings) -> Result<Scene, Box<dyn error::Error>> {
+    if let Some(seed) = settings.seed {
+        seed_random(seed);
+    }
+    let mut scene = match settings.scene {
+        SceneKind::Random => random()?,
+        SceneKind::Materials => materials(match &settings.normal_map {
+            Some(path) => Some(Arc::new(ImageTexture::from_ppm(path)?)),
+            None => None,
+        }),
+        SceneKind::Cutouts => cutouts(match &settings.opacity_map {
+            Some(path) => Some(Arc::new(ImageTexture::from_ppm(path)?)),
+            None => None,
+        }),
+        SceneKind::Caustics => caustics(),
+    };
+    if !settings.lights.is_empty() {
+        scene.lights = settings.lights.clone();
+    }
+    Ok(scene)
+}
+
+/// Large ground sphere covered with small random ones around three big spheres.
+pub fn random() -> Result<Scene, TrackError> {
+    let mut world: Vec<BoxedHittable> = vec![];
+
+    let ground_material: SharedMaterial = Arc::new(Box::new(Lambertian {
+        albedo: Color {
+            x: 0.5,
+            y: 0.5,
+            z: 0.5,
+        },
+    }));
+
+    world.push(Box::new(Sphere {
+        center: Point3 {
+            x: 0.0,
+            y: -1000.0,
+            z: 0.0,
+        },
+        radius: 1000.0,
+        material: Arc::clone(&ground_material),
+    }));
+
+    let material1: SharedMaterial = Arc::new(Box::new(Dielectric {
+        ir: 1.5,
+        dispersion: Some(Dispersion::bk7()),
+        absorption: None,
+    }));
+    world.push(Box::new(Sphere {
+        center: Point3 {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        },
+        radius: 1.0,
+        material: material1,
+    }));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = random_float();
+            let center = Point3 {
+                x: (a as Float) + 0.9 * random_float(),
+                y: 0.2,
+                z: (b as Float) + 0.9 * random_float(),
+            };
+
+            if (center
+                - Point3 {
+                    x: 4.0,
+                    y: 0.2,
+                    z: 0.0,
+                })
+            .len()
+                > 0.9
+            {
+                let material: SharedMaterial = if choose_mat < 0.8 {
+                    Arc::new(Box::new(Lambertian {
+                        albedo: Color::random(),
+                    }))
+                } else if choose_mat < 0.95 {
+                    Arc::new(Box::new(Metal {
+                        albedo: Color::random(),
+                        fuzzines: random_float_in_range(0.0, 0.5),
+                    }))
+                } else {
+                    Arc::new(Box::new(Dielectric {
+                        ir: 1.5,
+                        dispersion: Some(Dispersion::Cauchy {
+                            a: 1.488,
+                            b: 0.0042,
+                        }),
+                        absorption: None,
+                    }))
+                };
+                world.push(Box::new(Sphere {
+                    center,
+                    material,
+                    radius: 0.2,
+                }))
+            }
+        }
+    }
+
+    let material2: SharedMaterial = Arc::new(Box::new(Lambertian {
+        albedo: Color {
+            x: 0.4,
+            y: 0.2,
+            z: 0.1,
+        },
+    }));
+    world.push(Box::new(Sphere {
+        center: Point3 {
+            x: -4.0,
+            y: 1.0,
+            z: 0.0,
+        },
+        radius: 1.0,
+        material: material2,
+    }));
+
+    let material3: SharedMaterial = Arc::new(Box::new(Metal {
+        albedo: Color {
+            x: 0.7,
+            y: 0.6,
+            z: 0.5,
+        },
+        fuzzines: 0.0,
+    }));
+    let bounce = Vec3 {
+        x: 0.0,
+        y: 1.5,
+        z: 0.0,
+    };
+    world.push(Box::new(Animated {
+        object: Box::new(Sphere {
+            center: Point3 {
+                x: 4.0,
+                y: 1.0,
+                z: 0.0,
+            },
+            radius: 1.0,
+            material: material3,
+        }),
+        transform: Transform {
+            translation: Track::new(
+                Interpolation::CatmullRom,
+                &[
+                    (1.0, 0.0 * bounce),
+                    (12.0, bounce),
+                    (24.0, 0.0 * bounce),
+                    (36.0, bounce),
+                    (48.0, 0.0 * bounce),
+                ],
+            )?,
+            rotation: Track::constant(Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            }),
+            scale: Track::constant(1.0),
+        },
+    }));
+
+    let mut scene = Scene::new(
+        world,
+        View {
+            look_from: Point3 {
+                x: 13.0,
+                y: 2.0,
+                z: 3.0,
+            },
+            look_at: Point3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            vfov: 20.0,
+        },
+    );
+    scene.sky_bounds = Some(BoundingSphere {
+        center: Point3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        radius: 17.0,
+    });
+    scene.lights = vec![sun()];
+    scene.camera_animation = Some(orbit(scene.view)?);
+    return Ok(scene);
+}
+
+/// Slow orbit around `view.look_at` over 48 frames while zooming in.
+fn orbit(view: View) -> Result<CameraAnimation, TrackError> {
+    let View {
+        look_from,
+        look_at,
+        vfov,
+    } = view;
+    let orbit = |degrees: Float| {
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        Point3 {
+            x: cos * look_from.x + sin * look_from.z,
+            y: look_from.y,
+            z: -sin * look_from.x + cos * look_from.z,
+        }
+    };
+    Ok(CameraAnimation {
+        look_from: Track::new(
+            Interpolation::CatmullRom,
+            &[
+                (1.0, orbit(0.0)),
+                (16.0, orbit(-20.0)),
+                (32.0, orbit(-40.0)),
+                (48.0, orbit(-60.0)),
+            ],
+        )?,
+        look_at: Track::constant(look_at),
+        vfov: Track::new(Interpolation::Bezier, &[(1.0, vfov), (48.0, 0.75 * vfov)])?,
+    })
+}
+
+/// Rows of spheres showing off the physically based materials, on a tiled floor. The tiles are
+/// bump mapped unless a tangent-space `normal_map` is given for the floor.
+pub fn materials(normal_map: Option<Arc<ImageTexture>>) -> Scene {
+    let mut world: Vec<BoxedHittable> = vec![];
+
+    let map = match normal_map {
+        Some(texture) => NormalMap::TangentSpace(texture),
+        None => NormalMap::Bump {
+            height: Arc::new(Checker {
+                even: Color {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                odd: Color {
+                    x: 1.0,
+                    y: 1.0,
+                    z: 1.0,
+                },
+                scale: 1.0,
+            }),
+            scale: 0.005,
+        },
+    };
+    world.push(Box::new(Sphere {
+        center: Point3 {
+            x: 0.0,
+            y: -1000.0,
+            z: 0.0,
+        },
+        radius: 1000.0,
+        material: Arc::new(Box::new(NormalMapped {
+            material: Arc::new(Box::new(Lambertian {
+                albedo: Color {
+                    x: 0.5,
+                    y: 0.5,
+                    z: 0.5,
+                },
+            })),
+            map,
+        })),
+    }));
+
+    let brushed = Conductor::aluminium(0.0);
+    let materials: Vec<SharedMaterial> = vec![
+        Arc::new(Box::new(Conductor::gold(0.2))),
+        Arc::new(Box::new(Conductor::copper(0.35))),
+        Arc::new(Box::new(Conductor::new(brushed.eta, brushed.k, 0.1, 0.5))),
+        Arc::new(Box::new(Conductor::silver(0.05))),
+        Arc::new(Box::new(RoughDielectric {
+            absorption: Some(Absorption {
+                color: Color {
+                    x: 0.4,
+                    y: 0.8,
+                    z: 0.6,
+                },
+                distance: 1.0,
+            }),
+            ..RoughDielectric::new(1.5, 0.3, 0.3)
+        })),
+    ];
+    let principled: Vec<SharedMaterial> = vec![
+        Arc::new(Box::new(Principled {
+            base_color: Color {
+                x: 0.8,
+                y: 0.1,
+                z: 0.1,
+            },
+            roughness: 0.4,
+            ..Principled::default()
+        })),
+        Arc::new(Box::new(Principled {
+            base_color: Color {
+                x: 0.05,
+                y: 0.2,
+                z: 0.6,
+            },
+            metallic: 0.5,
+            roughness: 0.35,
+            clearcoat: 1.0,
+            ..Principled::default()
+        })),
+        Arc::new(Box::new(Principled {
+            base_color: Color {
+                x: 0.5,
+                y: 0.1,
+                z: 0.4,
+            },
+            roughness: 0.9,
+            sheen: 1.0,
+            ..Principled::default()
+        })),
+        Arc::new(Box::new(Principled {
+            base_color: Color {
+                x: 0.9,
+                y: 0.75,
+                z: 0.6,
+            },
+            subsurface: 0.6,
+            subsurface_color: Color {
+                x: 0.9,
+                y: 0.3,
+                z: 0.2,
+            },
+            ..Principled::default()
+        })),
+        Arc::new(Box::new(Principled::from_gltf(
+            Color {
+                x: 0.7,
+                y: 0.9,
+                z: 0.8,
+            },
+            0.0,
+            0.1,
+            1.0,
+            1.5,
+            (0.0, 0.0),
+        ))),
+    ];
+    let lambertian = |x: Float, y: Float, z: Float| -> SharedMaterial {
+        Arc::new(Box::new(Lambertian {
+            albedo: Color { x, y, z },
+        }))
+    };
+    let wood: SharedMaterial = Arc::new(Box::new(Mix {
+        a: lambertian(0.45, 0.25, 0.1),
+        b: lambertian(0.25, 0.12, 0.05),
+        weight: MixWeight::Texture(Arc::new(Checker {
+            even: Color {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            odd: Color {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            scale: 0.15,
+        })),
+    }));
+    let flakes: SharedMaterial = Arc::new(Box::new(Mix {
+        a: lambertian(0.05, 0.1, 0.5),
+        b: Arc::new(Box::new(Conductor::aluminium(0.3))),
+        weight: MixWeight::Scalar(0.3),
+    }));
+    let coat = |base: SharedMaterial| -> SharedMaterial {
+        Arc::new(Box::new(Coated {
+            base,
+            ir: 1.5,
+            roughness: 0.0,
+            tint: Color {
+                x: 1.0,
+                y: 0.97,
+                z: 0.9,
+            },
+        }))
+    };
+    let layered: Vec<SharedMaterial> = vec![
+        Arc::new(Box::new(Mix {
+            a: lambertian(0.8, 0.8, 0.8),
+            b: lambertian(0.1, 0.1, 0.1),
+            weight: MixWeight::Texture(Arc::new(Checker {
+                even: Color {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                odd: Color {
+                    x: 1.0,
+                    y: 1.0,
+                    z: 1.0,
+                },
+                scale: 0.35,
+            })),
+        })),
+        coat(Arc::clone(&wood)),
+        coat(Arc::clone(&flakes)),
+        Arc::new(Box::new(TwoSided {
+            material: coat(lambertian(0.7, 0.1, 0.1)),
+        })),
+        Arc::new(Box::new(Mix {
+            a: Arc::new(Box::new(Conductor::copper(0.1))),
+            b: Arc::new(Box::new(Dielectric {
+                ir: 2.42,
+                dispersion: Some(Dispersion::diamond()),
+                absorption: Some(Absorption {
+                    color: Color {
+                        x: 0.9,
+                        y: 0.75,
+                        z: 0.3,
+                    },
+                    distance: 1.0,
+                }),
+            })),
+            weight: MixWeight::Scalar(0.5),
+        })),
+    ];
+    for (row, materials) in [materials, principled, layered].into_iter().enumerate() {
+        let count = materials.len();
+        for (i, material) in materials.into_iter().enumerate() {
+            world.push(Box::new(Sphere {
+                center: Point3 {
+                    x: -3.0 * row as Float,
+                    y: 0.7 + 1.2 * row as Float,
+                    z: 1.6 * (i as Float - (count - 1) as Float / 2.0),
+                },
+                radius: 0.7,
+                material,
+            }));
+        }
+    }
+
+    let mut scene = Scene::new(
+        world,
+        View {
+            look_from: Point3 {
+                x: 13.0,
+                y: 2.0,
+                z: 3.0,
+            },
+            look_at: Point3 {
+                x: 0.0,
+                y: 1.5,
+                z: 0.0,
+            },
+            vfov: 20.0,
+        },
+    );
+    scene.sky_bounds = Some(BoundingSphere {
+        center: Point3 {
+            x: -3.0,
+            y: 1.5,
+            z: 0.0,
+        },
+        radius: 8.0,
+    });
+    scene.lights = vec![sun()];
+    return scene;
+}
+
+/// Textured quads with cutout masks in front of a few spheres: a fence with square holes, a
+/// sheet of foliage that is partially transparent unless an `opacity_map` is given for it, and a
+/// sphere with holes through which its inside shows.
+pub fn cutouts(opacity_map: Option<Arc<ImageTexture>>) -> Scene {
+    let mut world: Vec<BoxedHittable> = vec![];
+
+    world.push(Box::new(Sphere {
+        center: Point3 {
+            x: 0.0,
+            y: -1000.0,
+            z: 0.0,
+        },
+        radius: 1000.0,
+        material: Arc::new(Box::new(Lambertian {
+            albedo: Color {
+                x: 0.5,
+                y: 0.5,
+                z: 0.5,
+            },
+        })),
+    }));
+
+    let holes: Arc<Checker> = Arc::new(Checker {
+        even: Color {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        odd: Color {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        },
+        scale: 0.25,
+    });
+    world.push(Box::new(AlphaMasked {
+        object: Box::new(Quad {
+            corner: Point3 {
+                x: 2.0,
+                y: 0.0,
+                z: -4.0,
+            },
+            u: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 8.0,
+            },
+            v: Vec3 {
+                x: 0.0,
+                y: 1.5,
+                z: 0.0,
+            },
+            material: Arc::new(Box::new(Lambertian {
+                albedo: Color {
+                    x: 0.6,
+                    y: 0.4,
+                    z: 0.2,
+                },
+            })),
+        }),
+        opacity: holes.clone(),
+        mode: AlphaMode::Threshold(0.5),
+    }));
+
+    let (opacity, mode): (SharedTexture, AlphaMode) = match opacity_map {
+        Some(texture) => (texture, AlphaMode::Threshold(0.5)),
+        None => (
+            Arc::new(Checker {
+                even: Color {
+                    x: 0.3,
+                    y: 0.3,
+                    z: 0.3,
+                },
+                odd: Color {
+                    x: 0.8,
+                    y: 0.8,
+                    z: 0.8,
+                },
+                scale: 0.5,
+            }),
+            AlphaMode::Stochastic,
+        ),
+    };
+    world.push(Box::new(AlphaMasked {
+        object: Box::new(Quad {
+            corner: Point3 {
+                x: 4.5,
+                y: 0.0,
+                z: 0.5,
+            },
+            u: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 2.0,
+            },
+            v: Vec3 {
+                x: 0.0,
+                y: 2.0,
+                z: 0.0,
+            },
+            material: Arc::new(Box::new(Lambertian {
+                albedo: Color {
+                    x: 0.2,
+                    y: 0.6,
+                    z: 0.1,
+                },
+            })),
+        }),
+        opacity,
+        mode,
+    }));
+
+    let colors = [
+        Color {
+            x: 0.8,
+            y: 0.1,
+            z: 0.1,
+        },
+        Color {
+            x: 0.1,
+            y: 0.3,
+            z: 0.8,
+        },
+        Color {
+            x: 0.9,
+            y: 0.8,
+            z: 0.2,
+        },
+    ];
+    for (i, albedo) in colors.into_iter().enumerate() {
+        world.push(Box::new(Sphere {
+            center: Point3 {
+                x: -1.0,
+                y: 1.0,
+                z: 2.5 * (i as Float - 1.0),
+            },
+            radius: 1.0,
+            material: Arc::new(Box::new(Lambertian { albedo })),
+        }));
+    }
+
+    world.push(Box::new(AlphaMasked {
+        object: Box::new(Sphere {
+            center: Point3 {
+                x: 3.5,
+                y: 0.6,
+                z: -2.0,
+            },
+            radius: 0.6,
+            material: Arc::new(Box::new(Metal {
+                albedo: Color {
+                    x: 0.8,
+                    y: 0.8,
+                    z: 0.8,
+                },
+                fuzzines: 0.1,
+            })),
+        }),
+        opacity: holes,
+        mode: AlphaMode::Threshold(0.5),
+    }));
+
+    let mut scene = Scene::new(
+        world,
+        View {
+            look_from: Point3 {
+                x: 13.0,
+                y: 2.0,
+                z: 3.0,
+            },
+            look_at: Point3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            vfov: 20.0,
+        },
+    );
+    scene.sky_bounds = Some(BoundingSphere {
+        center: Point3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        radius: 6.0,
+    });
+    scene.lights = vec![sun()];
+    return scene;
+}
+
+/// Closed room lit only by a small ceiling panel and a lamp enclosed in a glass globe, with a
+/// glass ball casting a caustic on the floor. Most of the light reaches the camera through
+/// specular paths, which is hard on the path tracer and what `--integrator bdpt` is for.
+pub fn caustics() -> Scene {
+    let mut world: Vec<BoxedHittable> = vec![];
+
+    let white: SharedMaterial = Arc::new(Box::new(Lambertian {
+        albedo: Color {
+            x: 0.73,
+            y: 0.73,
+            z: 0.73,
+        },
+    }));
+    let red: SharedMaterial = Arc::new(Box::new(Lambertian {
+        albedo: Color {
+            x: 0.65,
+            y: 0.05,
+            z: 0.05,
+        },
+    }));
+    let green: SharedMaterial = Arc::new(Box::new(Lambertian {
+        albedo: Color {
+            x: 0.12,
+            y: 0.45,
+            z: 0.15,
+        },
+    }));
+    let glass: SharedMaterial = Arc::new(Box::new(Dielectric {
+        ir: 1.5,
+        dispersion: None,
+        absorption: None,
+    }));
+
+    // Floor, ceiling, back, left and right walls of a 5 x 5 x 5 room open towards the camera.
+    let walls = [
+        ((-2.5, 0.0, -2.5), (0.0, 0.0, 5.0), (5.0, 0.0, 0.0), &white),
+        ((-2.5, 5.0, -2.5), (5.0, 0.0, 0.0), (0.0, 0.0, 5.0), &white),
+        ((-2.5, 0.0, -2.5), (5.0, 0.0, 0.0), (0.0, 5.0, 0.0), &white),
+        ((-2.5, 0.0, -2.5), (0.0, 5.0, 0.0), (0.0, 0.0, 5.0), &red),
+        ((2.5, 0.0, -2.5), (0.0, 0.0, 5.0), (0.0, 5.0, 0.0), &green),
+    ];
+    for (corner, u, v, material) in walls {
+        world.push(Box::new(Quad {
+            corner: Point3 {
+                x: corner.0,
+                y: corner.1,
+                z: corner.2,
+            },
+            u: Vec3 {
+                x: u.0,
+                y: u.1,
+                z: u.2,
+            },
+            v: Vec3 {
+                x: v.0,
+                y: v.1,
+                z: v.2,
+            },
+            material: Arc::clone(material),
+        }));
+    }
+
+    world.push(Box::new(Quad {
+        corner: Point3 {
+            x: -0.5,
+            y: 4.99,
+            z: -0.5,
+        },
+        u: Vec3 {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        v: Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        },
+        material: Arc::new(Box::new(DiffuseLight {
+            emit: Color {
+                x: 15.0,
+                y: 15.0,
+                z: 15.0,
+            },
+        })),
+    }));
+
+    world.push(Box::new(Sphere {
+        center: Point3 {
+            x: -1.0,
+            y: 1.0,
+            z: -0.5,
+        },
+        radius: 1.0,
+        material: Arc::clone(&glass),
+    }));
+
+    let lamp = Point3 {
+        x: 1.3,
+        y: 0.6,
+        z: 0.8,
+    };
+    world.push(Box::new(Sphere {
+        center: lamp,
+        radius: 0.6,
+        material: Arc::clone(&glass),
+    }));
+    world.push(Box::new(Sphere {
+        center: lamp,
+        radius: 0.15,
+        material: Arc::new(Box::new(DiffuseLight {
+            emit: Color {
+                x: 40.0,
+                y: 28.0,
+                z: 12.0,
+            },
+        })),
+    }));
+
+    let mut scene = Scene::new(
+        world,
+        View {
+            look_from: Point3 {
+                x: 0.0,
+                y: 2.5,
+                z: 10.0,
+            },
+            look_at: Point3 {
+                x: 0.0,
+                y: 2.5,
+                z: 0.0,
+            },
+            vfov: 32.0,
+        },
+    );
+    scene.background = Background::Uniform(Color {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    });
+    return scene;
+}
+
+/// Sun shining from high up on the left, shared by the outdoor scenes.
+fn sun() -> Light {
+    Light::Directional {
+        direction: Vec3 {
+            x: -1.0,
+            y: -2.0,
+            z: -0.5,
+        },
+        irradiance: Color {
+            x: 1.8,
+            y: 1.7,
+            z: 1.55,
+        },
+        angular_diameter: degrees_to_radians(0.53),
+    }
+}
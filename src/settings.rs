@@ -0,0 +1,409 @@
+use std::str::FromStr;
+
+use crate::{
+    camera::{Aperture, FisheyeMapping, Lens},
+    float::Float,
+    helpers::degrees_to_radians,
+    integrator::{DebugSettings, DebugView, DepthLimits, PhotonSettings},
+    light::{Falloff, Light},
+    vec3::{Color, Vec3},
+};
+
+#[derive(Copy, Clone)]
+pub enum CameraKind {
+    Perspective,
+    Orthographic,
+    Panorama,
+    Fisheye(FisheyeMapping),
+    Stereo,
+}
+
+impl CameraKind {
+    fn parse(value: &str) -> Option<CameraKind> {
+        match value {
+            "perspective" => Some(CameraKind::Perspective),
+            "orthographic" => Some(CameraKind::Orthographic),
+            "panorama" => Some(CameraKind::Panorama),
+            "fisheye" => Some(CameraKind::Fisheye(FisheyeMapping::Equidistant)),
+            "fisheye-equisolid" => Some(CameraKind::Fisheye(FisheyeMapping::Equisolid)),
+            "stereo" => Some(CameraKind::Stereo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum IntegratorKind {
+    Path,
+    Bidirectional,
+    Photon,
+    /// Photon mapping with gather radii shrinking over several passes.
+    ProgressivePhoton,
+    Debug(DebugView),
+    /// Noise free preview with delta lights and ideal specular materials only.
+    Whitted,
+}
+
+#[derive(Copy, Clone)]
+pub enum SceneKind {
+    Random,
+    Materials,
+    Cutouts,
+    Caustics,
+}
+
+pub struct RenderSettings {
+    pub scene: SceneKind,
+    pub image_width: i32,
+    pub aspect_ratio: Float,
+    pub samples_per_pixel: i32,
+    pub depths: DepthLimits,
+    /// Largest radiance a single sample may contribute, see `PathTracer::clamp`.
+    pub clamp: Option<Float>,
+    /// Inclusive range of frames to render as an image sequence instead of a single image.
+    pub frames: Option<(i32, i32)>,
+    pub aovs: bool,
+    pub spectral: bool,
+    /// Time the ways of intersecting the scene instead of rendering it.
+    pub benchmark: bool,
+    /// Draws the same random numbers on every run, rendering the same image.
+    pub seed: Option<u64>,
+    pub integrator: IntegratorKind,
+    pub photons: PhotonSettings,
+    pub debug: DebugSettings,
+    /// Replace the default lights of the scene when any are given.
+    pub lights: Vec<Light>,
+    pub camera: CameraKind,
+    pub lens: Lens,
+    pub aperture_mask: Option<String>,
+    pub normal_map: Option<String>,
+    pub opacity_map: Option<String>,
+    pub focal_length: Option<Float>,
+    pub f_number: Option<Float>,
+    pub auto_focus: bool,
+    pub sensor_size: Option<(Float, Float)>,
+    pub scene_units_per_mm: Option<Float>,
+}
+
+/// Arguments of the `diff` subcommand:
+/// `diff <reference.ppm> <test.ppm> [--heatmap <path>] [--max-flip <error>]`.
+pub struct DiffSettings {
+    pub reference: String,
+    pub test: String,
+    /// Where the heatmap of the FLIP-style error is written.
+    pub heatmap: String,
+    /// Largest mean FLIP-style error accepted before `diff` fails.
+    pub max_flip: Option<Float>,
+}
+
+impl DiffSettings {
+    /// `None` unless `args` run the `diff` subcommand on two images.
+    pub fn from_args(args: &[String]) -> Option<DiffSettings> {
+        let mut args = args.iter().skip(1);
+        if args.next()? != "diff" {
+            return None;
+        }
+        let mut images = vec![];
+        let mut heatmap = String::from("diff.ppm");
+        let mut max_flip = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--heatmap" => heatmap = args.next()?.clone(),
+                "--max-flip" => max_flip = Some(parse_next(&mut args)?),
+                path => images.push(path.to_string()),
+            }
+        }
+        let [reference, test]: [String; 2] = images.try_into().ok()?;
+        Some(DiffSettings {
+            reference,
+            test,
+            heatmap,
+            max_flip,
+        })
+    }
+}
+
+impl RenderSettings {
+    pub fn from_args(args: &[String]) -> RenderSettings {
+        let mut settings = RenderSettings {
+            scene: SceneKind::Random,
+            image_width: 400,
+            aspect_ratio: 16.0 / 9.0,
+            samples_per_pixel: 500,
+            depths: DepthLimits::default(),
+            clamp: None,
+            frames: None,
+            aovs: false,
+            spectral: false,
+            benchmark: false,
+            seed: None,
+            integrator: IntegratorKind::Path,
+            photons: PhotonSettings::default(),
+            debug: DebugSettings::default(),
+            lights: vec![],
+            camera: CameraKind::Perspective,
+            lens: Lens::default(),
+            aperture_mask: None,
+            normal_map: None,
+            opacity_map: None,
+            focal_length: None,
+            f_number: None,
+            auto_focus: false,
+            sensor_size: None,
+            scene_units_per_mm: None,
+        };
+        let mut aperture_rotation = 0.0;
+        let mut samples_per_pixel = None;
+        let mut light_falloff = Falloff::InverseSquare;
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--aovs" => settings.aovs = true,
+                "--spectral" => settings.spectral = true,
+                "--benchmark" => settings.benchmark = true,
+                "--scene" => match args.next().map(|v| v.as_str()) {
+                    Some("random") => settings.scene = SceneKind::Random,
+                    Some("materials") => settings.scene = SceneKind::Materials,
+                    Some("cutouts") => settings.scene = SceneKind::Cutouts,
+                    Some("caustics") => settings.scene = SceneKind::Caustics,
+                    _ => {}
+                },
+                "--integrator" => match args.next().map(|v| v.as_str()) {
+                    Some("path") => settings.integrator = IntegratorKind::Path,
+                    Some("bdpt") => settings.integrator = IntegratorKind::Bidirectional,
+                    Some("photon") => settings.integrator = IntegratorKind::Photon,
+                    Some("sppm") => settings.integrator = IntegratorKind::ProgressivePhoton,
+                    Some("whitted") => settings.integrator = IntegratorKind::Whitted,
+                    Some("normal") => {
+                        settings.integrator = IntegratorKind::Debug(DebugView::Normal)
+                    }
+                    Some("depth") => settings.integrator = IntegratorKind::Debug(DebugView::Depth),
+                    Some("albedo") => {
+                        settings.integrator = IntegratorKind::Debug(DebugView::Albedo)
+                    }
+                    Some("ao") => {
+                        settings.integrator = IntegratorKind::Debug(DebugView::AmbientOcclusion)
+                    }
+                    Some("wireframe") => {
+                        settings.integrator = IntegratorKind::Debug(DebugView::Wireframe)
+                    }
+                    Some("bounces") => {
+                        settings.integrator = IntegratorKind::Debug(DebugView::Bounces)
+                    }
+                    _ => {}
+                },
+                "--photons" => {
+                    if let Some(photons) = parse_next::<usize>(&mut args).filter(|p| *p > 0) {
+                        settings.photons.photons = photons;
+                    }
+                }
+                "--photon-radius" => {
+                    if let Some(radius) = parse_next::<Float>(&mut args).filter(|r| *r > 0.0) {
+                        settings.photons.radius = radius;
+                    }
+                }
+                "--photon-passes" => {
+                    if let Some(passes) = parse_next::<usize>(&mut args).filter(|p| *p > 0) {
+                        settings.photons.passes = passes;
+                    }
+                }
+                "--seed" => settings.seed = parse_next(&mut args),
+                "--samples" => samples_per_pixel = parse_next(&mut args).filter(|s: &i32| *s > 0),
+                "--ao-rays" => {
+                    if let Some(rays) = parse_next::<u32>(&mut args).filter(|r| *r > 0) {
+                        settings.debug.ao_rays = rays;
+                    }
+                }
+                "--ao-distance" => {
+                    if let Some(distance) = parse_next::<Float>(&mut args).filter(|d| *d > 0.0) {
+                        settings.debug.ao_distance = distance;
+                    }
+                }
+                "--depth-range" => {
+                    if let Some(far) = parse_next::<Float>(&mut args).filter(|f| *f > 0.0) {
+                        settings.debug.far = far;
+                    }
+                }
+                "--bounce-range" => {
+                    if let Some(range) = parse_next::<i32>(&mut args).filter(|r| *r > 0) {
+                        settings.debug.bounce_range = range;
+                    }
+                }
+                "--point-light" => {
+                    if let Some([x, y, z, r, g, b]) = parse_numbers(&mut args) {
+                        settings.lights.push(Light::Point {
+                            position: Vec3 { x, y, z },
+                            intensity: Color { x: r, y: g, z: b },
+                            falloff: Falloff::InverseSquare,
+                        });
+                    }
+                }
+                "--spot-light" => {
+                    if let Some([x, y, z, dx, dy, dz, r, g, b, inner, outer]) =
+                        parse_numbers(&mut args)
+                    {
+                        settings.lights.push(Light::Spot {
+                            position: Vec3 { x, y, z },
+                            direction: Vec3 {
+                                x: dx,
+                                y: dy,
+                                z: dz,
+                            },
+                            intensity: Color { x: r, y: g, z: b },
+                            falloff: Falloff::InverseSquare,
+                            inner_angle: degrees_to_radians(inner),
+                            outer_angle: degrees_to_radians(outer),
+                        });
+                    }
+                }
+                "--sun" => {
+                    if let Some([x, y, z, r, g, b, diameter]) = parse_numbers(&mut args) {
+                        settings.lights.push(Light::Directional {
+                            direction: Vec3 { x, y, z },
+                            irradiance: Color { x: r, y: g, z: b },
+                            angular_diameter: degrees_to_radians(diameter),
+                        });
+                    }
+                }
+                "--falloff" => match args.next().map(|v| v.as_str()) {
+                    Some("none") => light_falloff = Falloff::None,
+                    Some("linear") => light_falloff = Falloff::Linear,
+                    Some("square") => light_falloff = Falloff::InverseSquare,
+                    _ => {}
+                },
+                "--camera" => {
+                    if let Some(camera) = args.next().and_then(|v| CameraKind::parse(v)) {
+                        settings.camera = camera;
+                    }
+                }
+                "--aperture-blades" => {
+                    if let Some(blades) = parse_next::<u32>(&mut args).filter(|b| *b >= 3) {
+                        settings.lens.aperture = Aperture::Polygon {
+                            blades,
+                            rotation: 0.0,
+                        };
+                    }
+                }
+                "--aperture-rotation" => {
+                    if let Some(angle) = parse_next(&mut args) {
+                        aperture_rotation = angle;
+                    }
+                }
+                "--aperture-mask" => settings.aperture_mask = args.next().cloned(),
+                "--normal-map" => settings.normal_map = args.next().cloned(),
+                "--opacity-map" => settings.opacity_map = args.next().cloned(),
+                "--anamorphic" => {
+                    if let Some(squeeze) = parse_next::<Float>(&mut args).filter(|s| *s > 0.0) {
+                        settings.lens.anamorphic_squeeze = squeeze;
+                    }
+                }
+                "--tilt" => {
+                    if let Some(tilt) = parse_next(&mut args) {
+                        settings.lens.tilt = tilt;
+                    }
+                }
+                "--shift" => {
+                    if let Some((x, y)) = args.next().and_then(|v| v.split_once(',')) {
+                        if let (Ok(x), Ok(y)) = (x.parse(), y.parse()) {
+                            settings.lens.shift = (x, y);
+                        }
+                    }
+                }
+                "--chromatic-aberration" => {
+                    if let Some(amount) = parse_next(&mut args) {
+                        settings.lens.chromatic_aberration = amount;
+                    }
+                }
+                "--focal-length" => settings.focal_length = parse_next(&mut args),
+                "--f-number" => settings.f_number = parse_next(&mut args),
+                "--max-depth" => {
+                    if let Some(depth) = parse_next(&mut args) {
+                        settings.depths.total = depth;
+                    }
+                }
+                "--diffuse-depth" => {
+                    if let Some(depth) = parse_next(&mut args) {
+                        settings.depths.diffuse = depth;
+                    }
+                }
+                "--specular-depth" => {
+                    if let Some(depth) = parse_next(&mut args) {
+                        settings.depths.specular = depth;
+                    }
+                }
+                "--transmission-depth" => {
+                    if let Some(depth) = parse_next(&mut args) {
+                        settings.depths.transmission = depth;
+                    }
+                }
+                "--volume-depth" => {
+                    if let Some(depth) = parse_next(&mut args) {
+                        settings.depths.volume = depth;
+                    }
+                }
+                "--roulette-depth" => {
+                    if let Some(depth) = parse_next(&mut args) {
+                        settings.depths.roulette_after = depth;
+                    }
+                }
+                "--clamp" => settings.clamp = parse_next(&mut args).filter(|c: &Float| *c > 0.0),
+                "--frames" => {
+                    if let Some((first, last)) = args.next().and_then(|v| v.split_once("..")) {
+                        if let (Ok(first), Ok(last)) = (first.parse(), last.parse()) {
+                            settings.frames = Some((first, last));
+                        }
+                    }
+                }
+                "--autofocus" => settings.auto_focus = true,
+                "--sensor" => {
+                    if let Some((w, h)) = args.next().and_then(|v| v.split_once('x')) {
+                        if let (Ok(w), Ok(h)) = (w.parse(), h.parse()) {
+                            settings.sensor_size = Some((w, h));
+                        }
+                    }
+                }
+                "--units-per-mm" => settings.scene_units_per_mm = parse_next(&mut args),
+                value => {
+                    if let Ok(width) = value.parse::<i32>() {
+                        settings.image_width = width;
+                    }
+                }
+            }
+        }
+        if let Aperture::Polygon { rotation, .. } = &mut settings.lens.aperture {
+            *rotation = aperture_rotation;
+        }
+        for light in settings.lights.iter_mut() {
+            if let Light::Point { falloff, .. } | Light::Spot { falloff, .. } = light {
+                *falloff = light_falloff;
+            }
+        }
+        // Previews converge in a handful of samples, keep them quick unless asked otherwise.
+        settings.samples_per_pixel = samples_per_pixel.unwrap_or(match settings.integrator {
+            IntegratorKind::Debug(_) | IntegratorKind::Whitted => 16,
+            _ => settings.samples_per_pixel,
+        });
+        settings
+    }
+
+    pub fn image_height(&self) -> i32 {
+        ((self.image_width as Float) / self.aspect_ratio) as i32
+    }
+}
+
+/// Parses exactly `N` comma separated numbers, like `0,5,0,10,10,10`.
+fn parse_numbers<'a, const N: usize>(
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Option<[Float; N]> {
+    let numbers: Vec<Float> = args
+        .next()?
+        .split(',')
+        .map(|v| v.parse::<Float>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    numbers.try_into().ok()
+}
+
+fn parse_next<'a, T: FromStr>(args: &mut impl Iterator<Item = &'a String>) -> Option<T> {
+    args.next().and_then(|v| v.parse::<T>().ok())
+}
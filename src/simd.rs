@@ -0,0 +1,304 @@
+//! Four-lane `Float` vectors for structure of arrays math over ray packets, and the SSE kernels
+//! of single `Vec3`s. The lanes are backed by SSE on x86_64 when the `simd` feature is enabled
+//! and `Float` is `f32`, and by plain arrays otherwise; `Vec3` uses its own scalar code in the
+//! latter case. Every lane rounds exactly like the scalar operation it stands for, so both give
+//! identical results.
+
+use std::ops;
+
+use crate::vec3::Vec3;
+
+pub use lanes::Floatx4;
+
+#[cfg(all(feature = "simd", not(feature = "f64"), target_arch = "x86_64"))]
+mod lanes {
+    use std::{arch::x86_64::*, ops};
+
+    // SAFETY: every intrinsic used in this module belongs to SSE or SSE2, which all x86_64
+    // processors support.
+
+    #[derive(Copy, Clone)]
+    pub struct Floatx4(__m128);
+
+    /// Result of a lane-wise comparison.
+    #[derive(Copy, Clone)]
+    pub struct Mask4(__m128);
+
+    impl Floatx4 {
+        pub fn splat(value: f32) -> Floatx4 {
+            Floatx4(unsafe { _mm_set1_ps(value) })
+        }
+
+        pub fn new(lanes: [f32; 4]) -> Floatx4 {
+            Floatx4(unsafe { _mm_loadu_ps(lanes.as_ptr()) })
+        }
+
+        pub fn min(self, rhs: Floatx4) -> Floatx4 {
+            Floatx4(unsafe { _mm_min_ps(self.0, rhs.0) })
+        }
+
+        pub fn max(self, rhs: Floatx4) -> Floatx4 {
+            Floatx4(unsafe { _mm_max_ps(self.0, rhs.0) })
+        }
+
+        pub fn sqrt(self) -> Floatx4 {
+            Floatx4(unsafe { _mm_sqrt_ps(self.0) })
+        }
+
+        pub fn lt(self, rhs: Floatx4) -> Mask4 {
+            Mask4(unsafe { _mm_cmplt_ps(self.0, rhs.0) })
+        }
+
+        pub fn le(self, rhs: Floatx4) -> Mask4 {
+            Mask4(unsafe { _mm_cmple_ps(self.0, rhs.0) })
+        }
+    }
+
+    impl Mask4 {
+        /// One bit per lane, set where the comparison held, with lane zero in the lowest bit.
+        pub fn bits(self) -> u32 {
+            (unsafe { _mm_movemask_ps(self.0) }) as u32
+        }
+    }
+
+    impl ops::Add for Floatx4 {
+        type Output = Floatx4;
+
+        fn add(self, rhs: Floatx4) -> Floatx4 {
+            Floatx4(unsafe { _mm_add_ps(self.0, rhs.0) })
+        }
+    }
+
+    impl ops::Sub for Floatx4 {
+        type Output = Floatx4;
+
+        fn sub(self, rhs: Floatx4) -> Floatx4 {
+            Floatx4(unsafe { _mm_sub_ps(self.0, rhs.0) })
+        }
+    }
+
+    impl ops::Mul for Floatx4 {
+        type Output = Floatx4;
+
+        fn mul(self, rhs: Floatx4) -> Floatx4 {
+            Floatx4(unsafe { _mm_mul_ps(self.0, rhs.0) })
+        }
+    }
+
+    impl ops::Div for Floatx4 {
+        type Output = Floatx4;
+
+        fn div(self, rhs: Floatx4) -> Floatx4 {
+            Floatx4(unsafe { _mm_div_ps(self.0, rhs.0) })
+        }
+    }
+}
+
+#[cfg(not(all(feature = "simd", not(feature = "f64"), target_arch = "x86_64")))]
+mod lanes {
+    use std::ops;
+
+    use crate::float::Float;
+
+    #[derive(Copy, Clone)]
+    pub struct Floatx4([Float; 4]);
+
+    /// Result of a lane-wise comparison.
+    #[derive(Copy, Clone)]
+    pub struct Mask4([bool; 4]);
+
+    impl Floatx4 {
+        pub fn splat(value: Float) -> Floatx4 {
+            Floatx4([value; 4])
+        }
+
+        pub fn new(lanes: [Float; 4]) -> Floatx4 {
+            Floatx4(lanes)
+        }
+
+        /// Like `minps`, returns `rhs` when either lane is NaN.
+        pub fn min(self, rhs: Floatx4) -> Floatx4 {
+            self.zip(rhs, |a, b| if a < b { a } else { b })
+        }
+
+        /// Like `maxps`, returns `rhs` when either lane is NaN.
+        pub fn max(self, rhs: Floatx4) -> Floatx4 {
+            self.zip(rhs, |a, b| if a > b { a } else { b })
+        }
+
+        pub fn sqrt(self) -> Floatx4 {
+            Floatx4(self.0.map(Float::sqrt))
+        }
+
+        pub fn lt(self, rhs: Floatx4) -> Mask4 {
+            Mask4([0, 1, 2, 3].map(|i| self.0[i] < rhs.0[i]))
+        }
+
+        pub fn le(self, rhs: Floatx4) -> Mask4 {
+            Mask4([0, 1, 2, 3].map(|i| self.0[i] <= rhs.0[i]))
+        }
+
+        fn zip(self, rhs: Floatx4, f: impl Fn(Float, Float) -> Float) -> Floatx4 {
+            Floatx4([0, 1, 2, 3].map(|i| f(self.0[i], rhs.0[i])))
+        }
+    }
+
+    impl Mask4 {
+        /// One bit per lane, set where the comparison held, with lane zero in the lowest bit.
+        pub fn bits(self) -> u32 {
+            (0..4).filter(|i| self.0[*i]).map(|i| 1 << i).sum()
+        }
+    }
+
+    impl ops::Add for Floatx4 {
+        type Output = Floatx4;
+
+        fn add(self, rhs: Floatx4) -> Floatx4 {
+            self.zip(rhs, |a, b| a + b)
+        }
+    }
+
+    impl ops::Sub for Floatx4 {
+        type Output = Floatx4;
+
+        fn sub(self, rhs: Floatx4) -> Floatx4 {
+            self.zip(rhs, |a, b| a - b)
+        }
+    }
+
+    impl ops::Mul for Floatx4 {
+        type Output = Floatx4;
+
+        fn mul(self, rhs: Floatx4) -> Floatx4 {
+            self.zip(rhs, |a, b| a * b)
+        }
+    }
+
+    impl ops::Div for Floatx4 {
+        type Output = Floatx4;
+
+        fn div(self, rhs: Floatx4) -> Floatx4 {
+            self.zip(rhs, |a, b| a / b)
+        }
+    }
+}
+
+/// Arithmetic of single `Vec3`s held in the first three lanes of an SSE register, which `vec3`
+/// uses instead of its scalar code. Each component is computed in the same order as there.
+#[cfg(all(feature = "simd", not(feature = "f64"), target_arch = "x86_64"))]
+pub mod vector {
+    use std::arch::x86_64::*;
+
+    use crate::vec3::Vec3;
+
+    // SAFETY: as above, every intrinsic belongs to SSE.
+
+    /// Lanes `(y, z, x, w)` of the register.
+    const YZX: i32 = 0b11_00_10_01;
+    /// Lanes `(z, x, y, w)` of the register.
+    const ZXY: i32 = 0b11_01_00_10;
+
+    #[inline(always)]
+    fn load(v: Vec3) -> __m128 {
+        unsafe { _mm_set_ps(0.0, v.z, v.y, v.x) }
+    }
+
+    #[inline(always)]
+    fn store(v: __m128) -> Vec3 {
+        let mut lanes = [0.0; 4];
+        unsafe { _mm_storeu_ps(lanes.as_mut_ptr(), v) };
+        Vec3 {
+            x: lanes[0],
+            y: lanes[1],
+            z: lanes[2],
+        }
+    }
+
+    #[inline(always)]
+    pub fn add(a: Vec3, b: Vec3) -> Vec3 {
+        store(unsafe { _mm_add_ps(load(a), load(b)) })
+    }
+
+    #[inline(always)]
+    pub fn sub(a: Vec3, b: Vec3) -> Vec3 {
+        store(unsafe { _mm_sub_ps(load(a), load(b)) })
+    }
+
+    #[inline(always)]
+    pub fn mul(a: Vec3, b: Vec3) -> Vec3 {
+        store(unsafe { _mm_mul_ps(load(a), load(b)) })
+    }
+
+    #[inline(always)]
+    pub fn scale(a: Vec3, s: f32) -> Vec3 {
+        store(unsafe { _mm_mul_ps(load(a), _mm_set1_ps(s)) })
+    }
+
+    #[inline(always)]
+    pub fn dot(a: Vec3, b: Vec3) -> f32 {
+        unsafe {
+            let products = _mm_mul_ps(load(a), load(b));
+            let xy = _mm_add_ss(products, _mm_shuffle_ps(products, products, 0b01));
+            _mm_cvtss_f32(_mm_add_ss(xy, _mm_movehl_ps(products, products)))
+        }
+    }
+
+    #[inline(always)]
+    pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+        unsafe {
+            let (a, b) = (load(a), load(b));
+            store(_mm_sub_ps(
+                _mm_mul_ps(_mm_shuffle_ps(a, a, YZX), _mm_shuffle_ps(b, b, ZXY)),
+                _mm_mul_ps(_mm_shuffle_ps(a, a, ZXY), _mm_shuffle_ps(b, b, YZX)),
+            ))
+        }
+    }
+}
+
+/// Four vectors in structure of arrays layout.
+#[derive(Copy, Clone)]
+pub struct Vec3x4 {
+    pub x: Floatx4,
+    pub y: Floatx4,
+    pub z: Floatx4,
+}
+
+impl Vec3x4 {
+    pub fn new(vectors: [Vec3; 4]) -> Vec3x4 {
+        Vec3x4 {
+            x: Floatx4::new(vectors.map(|v| v.x)),
+            y: Floatx4::new(vectors.map(|v| v.y)),
+            z: Floatx4::new(vectors.map(|v| v.z)),
+        }
+    }
+
+    pub fn splat(v: Vec3) -> Vec3x4 {
+        Vec3x4 {
+            x: Floatx4::splat(v.x),
+            y: Floatx4::splat(v.y),
+            z: Floatx4::splat(v.z),
+        }
+    }
+
+    /// Summed in the same order as `dot_product`.
+    pub fn dot(self, rhs: Vec3x4) -> Floatx4 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    /// Computed in the same order as `Vec3::len`.
+    pub fn len(self) -> Floatx4 {
+        self.dot(self).sqrt()
+    }
+}
+
+impl ops::Sub for Vec3x4 {
+    type Output = Vec3x4;
+
+    fn sub(self, rhs: Vec3x4) -> Vec3x4 {
+        Vec3x4 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
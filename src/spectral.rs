@@ -0,0 +1,105 @@
+use std::sync::OnceLock;
+
+use crate::{
+    float::Float,
+    helpers::random_float_in_range,
+    vec3::{Color, Vec3},
+};
+
+pub const MIN_WAVELENGTH: Float = 380.0;
+pub const MAX_WAVELENGTH: Float = 780.0;
+
+/// Picks a wavelength uniformly over the visible range, returning it with its pdf.
+pub fn sample_wavelength() -> (Float, Float) {
+    (
+        random_float_in_range(MIN_WAVELENGTH, MAX_WAVELENGTH),
+        1.0 / (MAX_WAVELENGTH - MIN_WAVELENGTH),
+    )
+}
+
+fn smoothstep(edge0: Float, edge1: Float, x: Float) -> Float {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Value at `wavelength` of a smooth spectrum reproducing an RGB reflectance. The blue, green
+/// and red basis functions sum to one everywhere, so white stays white and albedos stay below
+/// one.
+pub fn rgb_to_spectrum(c: Color, wavelength: Float) -> Float {
+    let blue = 1.0 - smoothstep(470.0, 520.0, wavelength);
+    let red = smoothstep(570.0, 620.0, wavelength);
+    let green = 1.0 - blue - red;
+    c.x * red + c.y * green + c.z * blue
+}
+
+fn gaussian(x: Float, mean: Float, sigma_low: Float, sigma_high: Float) -> Float {
+    let sigma = if x < mean { sigma_low } else { sigma_high };
+    let t = (x - mean) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+/// CIE 1931 2° color matching functions, using the multi-lobe fit of Wyman et al. 2013.
+pub fn cie_xyz(wavelength: Float) -> Vec3 {
+    let l = wavelength;
+    Vec3 {
+        x: 1.056 * gaussian(l, 599.8, 37.9, 31.0) + 0.362 * gaussian(l, 442.0, 16.0, 26.7)
+            - 0.065 * gaussian(l, 501.1, 20.4, 26.2),
+        y: 0.821 * gaussian(l, 568.8, 46.9, 40.5) + 0.286 * gaussian(l, 530.9, 16.3, 31.1),
+        z: 1.217 * gaussian(l, 437.0, 11.8, 36.0) + 0.681 * gaussian(l, 459.0, 26.0, 13.8),
+    }
+}
+
+/// Converts CIE XYZ to linear sRGB.
+pub fn xyz_to_rgb(xyz: Vec3) -> Color {
+    Color {
+        x: 3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        y: -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        z: 0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    }
+}
+
+/// RGB of a constant unit spectrum, used to white balance the film so that such a spectrum
+/// comes out as `(1, 1, 1)`.
+fn white_point() -> Color {
+    static WHITE: OnceLock<Color> = OnceLock::new();
+    *WHITE.get_or_init(|| {
+        let steps = 400;
+        let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / steps as Float;
+        let mut xyz = Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        for i in 0..steps {
+            xyz += &(step * cie_xyz(MIN_WAVELENGTH + (i as Float + 0.5) * step));
+        }
+        xyz_to_rgb(xyz)
+    })
+}
+
+/// Film response to a single-wavelength radiance sample drawn with probability density `pdf`.
+pub fn to_rgb(wavelength: Float, radiance: Float, pdf: Float) -> Color {
+    let rgb = xyz_to_rgb((radiance / pdf) * cie_xyz(wavelength));
+    let white = white_point();
+    Color {
+        x: rgb.x / white.x,
+        y: rgb.y / white.y,
+        z: rgb.z / white.z,
+    }
+}
+
+/// Color as seen by a ray: for rays carrying a wavelength, the value of its upsampled spectrum
+/// there, replicated in all three channels.
+pub fn upsample(c: Color, wavelength: Option<Float>) -> Color {
+    match wavelength {
+        Some(wavelength) => {
+            let value = rgb_to_spectrum(c, wavelength);
+            Color {
+                x: value,
+                y: value,
+                z: value,
+            }
+        }
+        None => c,
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use crate::{float::Float, hittable::HitRecord, image::Image, ppm::read_ppm, vec3::Color};
+
+/// Texture shared by the materials and objects reading it.
+pub type SharedTexture = Arc<dyn Texture + Send + Sync>;
+
+pub trait Texture {
+    fn value(&self, hit_record: &HitRecord) -> Color;
+}
+
+/// Alternating 3D cells of `even` and `odd`, each `scale` world units wide.
+pub struct Checker {
+    pub even: Color,
+    pub odd: Color,
+    pub scale: Float,
+}
+
+impl Texture for Checker {
+    fn value(&self, hit_record: &HitRecord) -> Color {
+        let p = hit_record.p / self.scale;
+        let cell = p.x.floor() as i64 + p.y.floor() as i64 + p.z.floor() as i64;
+        if cell % 2 == 0 {
+            self.even
+        } else {
+            self.odd
+        }
+    }
+}
+
+/// Image wrapped over the `(u, v)` parameterization of a surface, repeating outside `[0, 1]`.
+/// Values are returned as stored, without gamma decoding, which suits data such as normal and
+/// height maps.
+pub struct ImageTexture {
+    image: Image,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> ImageTexture {
+        ImageTexture { image }
+    }
+
+    pub fn from_ppm(path: &str) -> Result<ImageTexture, std::io::Error> {
+        Ok(ImageTexture::new(read_ppm(path)?))
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, hit_record: &HitRecord) -> Color {
+        let u = hit_record.u.rem_euclid(1.0);
+        let v = hit_record.v.rem_euclid(1.0);
+        let (width, height) = (self.image.width, self.image.height);
+        let column = ((u * width as Float) as i32).min(width - 1);
+        let row = (((1.0 - v) * height as Float) as i32).min(height - 1);
+        self.image.get(column, row)
+    }
+}
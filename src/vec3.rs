@@ -1,21 +1,84 @@
 use std::ops;
 
-use crate::helpers::random_f32;
+use crate::{float::Float, helpers::random_float};
+
+#[cfg(all(feature = "simd", not(feature = "f64"), target_arch = "x86_64"))]
+use crate::simd::vector as kernels;
+
+/// The arithmetic the operators and products below are built on, replaced by the SSE kernels of
+/// `simd::vector` when the `simd` feature is enabled.
+#[cfg(not(all(feature = "simd", not(feature = "f64"), target_arch = "x86_64")))]
+mod kernels {
+    use super::Vec3;
+    use crate::float::Float;
+
+    pub fn add(a: Vec3, b: Vec3) -> Vec3 {
+        Vec3 {
+            x: a.x + b.x,
+            y: a.y + b.y,
+            z: a.z + b.z,
+        }
+    }
+
+    pub fn sub(a: Vec3, b: Vec3) -> Vec3 {
+        Vec3 {
+            x: a.x - b.x,
+            y: a.y - b.y,
+            z: a.z - b.z,
+        }
+    }
+
+    pub fn mul(a: Vec3, b: Vec3) -> Vec3 {
+        Vec3 {
+            x: a.x * b.x,
+            y: a.y * b.y,
+            z: a.z * b.z,
+        }
+    }
+
+    pub fn scale(a: Vec3, s: Float) -> Vec3 {
+        Vec3 {
+            x: a.x * s,
+            y: a.y * s,
+            z: a.z * s,
+        }
+    }
+
+    pub fn dot(a: Vec3, b: Vec3) -> Float {
+        a.x * b.x + a.y * b.y + a.z * b.z
+    }
+
+    pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+        Vec3 {
+            x: a.y * b.z - a.z * b.y,
+            y: a.z * b.x - a.x * b.z,
+            z: a.x * b.y - a.y * b.x,
+        }
+    }
+}
 
 #[derive(Copy, Clone)]
 pub struct Vec3 {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
 }
 
 impl Vec3 {
-    pub fn len(&self) -> f32 {
-        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    pub fn len(&self) -> Float {
+        self.len_squared().sqrt()
     }
 
-    pub fn len_squared(&self) -> f32 {
-        self.x * self.x + self.y * self.y + self.z * self.z
+    pub fn len_squared(&self) -> Float {
+        kernels::dot(*self, *self)
+    }
+
+    pub fn abs(&self) -> Vec3 {
+        Vec3 {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        }
     }
 
     pub fn near_zero(&self) -> bool {
@@ -25,9 +88,9 @@ impl Vec3 {
 
     pub fn random() -> Vec3 {
         Vec3 {
-            x: random_f32(),
-            y: random_f32(),
-            z: random_f32(),
+            x: random_float(),
+            y: random_float(),
+            z: random_float(),
         }
     }
 }
@@ -36,7 +99,7 @@ pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
     v - 2.0 * dot_product(v, n) * n
 }
 
-pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: f32) -> Vec3 {
+pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: Float) -> Vec3 {
     let cos_theta = dot_product(-uv, n).min(1.0);
     let r_out_perp = etai_over_etat * (uv + cos_theta * n);
     let r_out_parallel = -((1.0 - r_out_perp.len_squared()).abs()).sqrt() * n;
@@ -46,31 +109,25 @@ pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: f32) -> Vec3 {
 pub type Point3 = Vec3;
 pub type Color = Vec3;
 
-impl ops::Mul<Vec3> for f32 {
+impl ops::Mul<Vec3> for Float {
     type Output = Vec3;
 
     fn mul(self, rhs: Vec3) -> Self::Output {
-        Vec3 {
-            x: rhs.x * self,
-            y: rhs.y * self,
-            z: rhs.z * self,
-        }
+        kernels::scale(rhs, self)
     }
 }
 
-impl ops::Mul<f32> for Vec3 {
+impl ops::Mul<Float> for Vec3 {
     type Output = Vec3;
 
-    fn mul(self, rhs: f32) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         rhs * self
     }
 }
 
-impl ops::MulAssign<f32> for Vec3 {
-    fn mul_assign(&mut self, rhs: f32) {
-        self.x *= rhs;
-        self.y *= rhs;
-        self.z *= rhs;
+impl ops::MulAssign<Float> for Vec3 {
+    fn mul_assign(&mut self, rhs: Float) {
+        *self = kernels::scale(*self, rhs);
     }
 }
 
@@ -78,24 +135,20 @@ impl ops::Mul<Vec3> for Vec3 {
     type Output = Vec3;
 
     fn mul(self, rhs: Vec3) -> Self::Output {
-        Vec3 {
-            x: self.x * rhs.x,
-            y: self.y * rhs.x,
-            z: self.z * rhs.z,
-        }
+        kernels::mul(self, rhs)
     }
 }
 
-impl ops::Div<f32> for Vec3 {
+impl ops::Div<Float> for Vec3 {
     type Output = Vec3;
 
-    fn div(self, rhs: f32) -> Self::Output {
+    fn div(self, rhs: Float) -> Self::Output {
         self * (1.0 / rhs)
     }
 }
 
-impl ops::DivAssign<f32> for Vec3 {
-    fn div_assign(&mut self, rhs: f32) {
+impl ops::DivAssign<Float> for Vec3 {
+    fn div_assign(&mut self, rhs: Float) {
         *self *= 1.0 / rhs
     }
 }
@@ -114,9 +167,7 @@ impl ops::Neg for Vec3 {
 
 impl ops::AddAssign<&Vec3> for Vec3 {
     fn add_assign(&mut self, rhs: &Vec3) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-        self.z += rhs.z;
+        *self = kernels::add(*self, *rhs);
     }
 }
 
@@ -124,11 +175,7 @@ impl ops::Add<Vec3> for Vec3 {
     type Output = Vec3;
 
     fn add(self, rhs: Vec3) -> Self::Output {
-        Vec3 {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
-        }
+        kernels::add(self, rhs)
     }
 }
 
@@ -136,11 +183,7 @@ impl ops::Sub<Vec3> for Vec3 {
     type Output = Vec3;
 
     fn sub(self, rhs: Vec3) -> Self::Output {
-        Vec3 {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-            z: self.z - rhs.z,
-        }
+        kernels::sub(self, rhs)
     }
 }
 
@@ -151,18 +194,33 @@ impl std::fmt::Display for &Vec3 {
 }
 
 pub fn cross_product(v1: Vec3, v2: Vec3) -> Vec3 {
-    Vec3 {
-        x: v1.y * v2.z - v1.z * v2.y,
-        y: v1.z * v2.x - v1.x * v2.z,
-        z: v1.x * v2.y - v1.y * v2.x,
-    }
+    kernels::cross(v1, v2)
 }
 
-pub fn dot_product(v1: Vec3, v2: Vec3) -> f32 {
-    v1.x * v2.x + v1.y * v2.y + v1.z * v2.z
+pub fn dot_product(v1: Vec3, v2: Vec3) -> Float {
+    kernels::dot(v1, v2)
 }
 
 pub fn unit_vector(v: Vec3) -> Vec3 {
     let l = v.len();
     v / l
 }
+
+/// Two unit tangents completing `n` to a right-handed orthonormal basis.
+pub fn orthonormal_basis(n: Vec3) -> (Vec3, Vec3) {
+    let sign = Float::copysign(1.0, n.z);
+    let a = -1.0 / (sign + n.z);
+    let b = n.x * n.y * a;
+    (
+        Vec3 {
+            x: 1.0 + sign * n.x * n.x * a,
+            y: sign * b,
+            z: -sign * n.x,
+        },
+        Vec3 {
+            x: b,
+            y: sign + n.y * n.y * a,
+            z: -n.y,
+        },
+    )
+}
@@ -0,0 +1,147 @@
+//! Tests of keyframe tracks and of objects posed by them.
+
+use std::sync::Arc;
+
+use rust_raytracer::{
+    animation::{Interpolation, Track, TrackError, Transform},
+    float::Float,
+    hittable::{Animated, Hittable, Sphere},
+    material::Lambertian,
+    ray::Ray,
+    vec3::{Color, Point3, Vec3},
+};
+
+fn vec3(x: Float, y: Float, z: Float) -> Vec3 {
+    Vec3 { x, y, z }
+}
+
+fn animated_sphere(center: Point3, radius: Float, transform: Transform) -> Animated {
+    Animated {
+        object: Box::new(Sphere {
+            center,
+            radius,
+            material: Arc::new(Box::new(Lambertian {
+                albedo: Color {
+                    x: 0.5,
+                    y: 0.5,
+                    z: 0.5,
+                },
+            })),
+        }),
+        transform,
+    }
+}
+
+#[test]
+fn empty_tracks_are_rejected() {
+    assert_eq!(
+        Track::<Float>::new(Interpolation::Linear, &[]).err(),
+        Some(TrackError::NoKeyframes)
+    );
+    assert_eq!(
+        Track::<Float>::from_keyframes(Interpolation::Bezier, vec![]).err(),
+        Some(TrackError::NoKeyframes)
+    );
+}
+
+#[test]
+fn curved_tracks_stay_within_their_control_points() {
+    for interpolation in [Interpolation::CatmullRom, Interpolation::Bezier] {
+        // Overshoots above 1 and below 0 around the middle keyframes.
+        let track = Track::new(
+            interpolation,
+            &[(0.0, 0.0), (1.0, 1.0), (2.0, 1.0), (3.0, 0.0), (4.0, 0.0)],
+        )
+        .unwrap();
+        let points = track.control_points();
+        let low = points.iter().copied().fold(Float::MAX, Float::min);
+        let high = points.iter().copied().fold(Float::MIN, Float::max);
+        for i in 0..=400 {
+            let value = track.sample(i as Float / 100.0);
+            assert!(
+                low <= value && value <= high,
+                "{} outside {} {}",
+                value,
+                low,
+                high
+            );
+        }
+    }
+}
+
+/// Turning a sphere away from the origin moves it, and its normals, with it.
+#[test]
+fn rotation_turns_objects_about_their_origin() {
+    let sphere = animated_sphere(
+        vec3(2.0, 0.0, 0.0),
+        0.5,
+        Transform {
+            translation: Track::constant(vec3(0.0, 0.0, 0.0)),
+            rotation: Track::constant(vec3(0.0, 90.0, 0.0)),
+            scale: Track::constant(1.0),
+        },
+    );
+    let ray = Ray {
+        origin: vec3(0.0, 0.0, 0.0),
+        direction: vec3(0.0, 0.0, -1.0),
+        time: 0.0,
+        wavelength: None,
+    };
+    let hit = sphere.hit(&ray, 0.001, Float::MAX).unwrap();
+    assert!((hit.t - 1.5).abs() < 1e-4);
+    assert!((hit.normal - vec3(0.0, 0.0, 1.0)).len() < 1e-4);
+    assert!((hit.geometric_normal - vec3(0.0, 0.0, 1.0)).len() < 1e-4);
+}
+
+/// The box of a moving, turning and growing object holds it at every time, so the `Bvh` can sort
+/// it among the others.
+#[test]
+fn bounding_box_holds_every_pose() {
+    let center = vec3(1.0, 0.5, 0.0);
+    let radius = 0.5;
+    let transform = || Transform {
+        translation: Track::new(
+            Interpolation::CatmullRom,
+            &[
+                (0.0, vec3(0.0, 0.0, 0.0)),
+                (10.0, vec3(0.0, 3.0, 1.0)),
+                (20.0, vec3(2.0, 0.0, 0.0)),
+            ],
+        )
+        .unwrap(),
+        rotation: Track::new(
+            Interpolation::Linear,
+            &[(0.0, vec3(0.0, 0.0, 0.0)), (20.0, vec3(30.0, 180.0, 0.0))],
+        )
+        .unwrap(),
+        scale: Track::new(Interpolation::Bezier, &[(0.0, 1.0), (20.0, 2.0)]).unwrap(),
+    };
+    let sphere = animated_sphere(center, radius, transform());
+    let bounds = sphere.bounding_box().unwrap();
+    for i in 0..=200 {
+        let pose = transform().at(i as Float / 10.0);
+        let world_center = pose.point_to_world(center);
+        let world_radius = pose.scale * radius;
+        for axis in [
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(0.0, 0.0, 1.0),
+        ] {
+            for p in [
+                world_center + world_radius * axis,
+                world_center - world_radius * axis,
+            ] {
+                assert!(
+                    bounds.min.x <= p.x
+                        && p.x <= bounds.max.x
+                        && bounds.min.y <= p.y
+                        && p.y <= bounds.max.y
+                        && bounds.min.z <= p.z
+                        && p.z <= bounds.max.z,
+                    "pose at {} leaves the box",
+                    i as Float / 10.0
+                );
+            }
+        }
+    }
+}
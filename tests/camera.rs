@@ -0,0 +1,128 @@
+//! Tests of the validation and auto-focus of `CameraBuilder`.
+
+use std::sync::Arc;
+
+use rust_raytracer::{
+    animation::{Interpolation, Track, Transform},
+    camera::{Camera, CameraError, CameraModel, Lens},
+    float::Float,
+    hittable::{Animated, Sphere},
+    material::Lambertian,
+    vec3::{Color, Point3},
+    BoxedHittable, World,
+};
+
+fn point(x: Float, y: Float, z: Float) -> Point3 {
+    Point3 { x, y, z }
+}
+
+fn sphere(center: Point3, radius: Float) -> BoxedHittable {
+    Box::new(Sphere {
+        center,
+        radius,
+        material: Arc::new(Box::new(Lambertian {
+            albedo: Color {
+                x: 0.5,
+                y: 0.5,
+                z: 0.5,
+            },
+        })),
+    })
+}
+
+#[test]
+fn nan_parameters_are_rejected() {
+    let nan = Float::NAN;
+    let error = |builder: rust_raytracer::camera::CameraBuilder| builder.build().err();
+    assert!(matches!(
+        error(Camera::builder().vertical_fov(nan)),
+        Some(CameraError::InvalidFieldOfView(_))
+    ));
+    assert!(matches!(
+        error(Camera::builder().focal_length(nan)),
+        Some(CameraError::InvalidFocalLength(_))
+    ));
+    assert!(matches!(
+        error(Camera::builder().sensor_size(nan, 24.0)),
+        Some(CameraError::InvalidSensorSize(..))
+    ));
+    assert!(matches!(
+        error(Camera::builder().aspect_ratio(nan)),
+        Some(CameraError::InvalidAspectRatio(_))
+    ));
+    assert!(matches!(
+        error(Camera::builder().aperture(nan)),
+        Some(CameraError::InvalidAperture(_))
+    ));
+    assert!(matches!(
+        error(Camera::builder().f_number(nan)),
+        Some(CameraError::InvalidAperture(_))
+    ));
+    assert!(matches!(
+        error(Camera::builder().focus_distance(nan)),
+        Some(CameraError::InvalidFocusDistance(_))
+    ));
+}
+
+/// With the lens shifted up, the center of the image sees the near sphere above the axis rather
+/// than the far one on it, and is focused on it.
+#[test]
+fn auto_focus_follows_the_lens_shift() {
+    let world = World::new(vec![
+        sphere(point(0.0, 0.0, -10.0), 1.0),
+        sphere(point(0.0, 2.0, -5.0), 1.0),
+    ]);
+    let camera = Camera::builder()
+        .vertical_fov(60.0)
+        .aspect_ratio(1.0)
+        .aperture(0.5)
+        .lens(Lens {
+            // Moves the center of the image 0.4 up at unit distance, towards the near sphere.
+            shift: (0.0, 0.4 / (2.0 * (30.0 as Float).to_radians().tan())),
+            ..Lens::default()
+        })
+        .auto_focus(&world, 0.0)
+        .build()
+        .unwrap();
+    // Rays through one point of the film all meet at the point in focus.
+    let ray = camera.get_ray(0.5, 0.5);
+    let in_focus = ray.origin + ray.direction;
+    // The near sphere is met 5.385 - 1 along the unit ray, at a depth of 4.385 / 1.077.
+    assert!(
+        (-in_focus.z - 4.07).abs() < 0.01,
+        "focused at {}",
+        -in_focus.z
+    );
+}
+
+/// An animated sphere coming towards the camera is focused on where it is at the time given.
+#[test]
+fn auto_focus_measures_the_scene_at_its_time() {
+    let world = World::new(vec![Box::new(Animated {
+        object: sphere(point(0.0, 0.0, 0.0), 1.0),
+        transform: Transform {
+            translation: Track::new(
+                Interpolation::Linear,
+                &[(0.0, point(0.0, 0.0, -10.0)), (1.0, point(0.0, 0.0, -5.0))],
+            )
+            .unwrap(),
+            rotation: Track::constant(point(0.0, 0.0, 0.0)),
+            scale: Track::constant(1.0),
+        },
+    })]);
+    for (time, depth) in [(0.0, 9.0), (1.0, 4.0)] {
+        let camera = Camera::builder()
+            .aperture(0.5)
+            .auto_focus(&world, time)
+            .build()
+            .unwrap();
+        let ray = camera.get_ray(0.5, 0.5);
+        let in_focus = ray.origin + ray.direction;
+        assert!(
+            (-in_focus.z - depth).abs() < 1e-3,
+            "focused at {} at time {}",
+            -in_focus.z,
+            time
+        );
+    }
+}
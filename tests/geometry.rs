@@ -0,0 +1,273 @@
+//! Tests of refraction against Snell's law, of the edge cases of ray and sphere intersection, of
+//! rays going through alpha masks and of ray packets.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use rust_raytracer::{
+    float::Float,
+    hittable::{AlphaMasked, AlphaMode, HitRecord, Hittable, RayPacket, Sphere},
+    material::Lambertian,
+    random_float,
+    ray::Ray,
+    render::build_camera,
+    scenes, seed_random,
+    texture::Texture,
+    vec3::{cross_product, dot_product, refract, unit_vector, Color, Vec3},
+    RenderSettings,
+};
+
+fn vec3(x: Float, y: Float, z: Float) -> Vec3 {
+    Vec3 { x, y, z }
+}
+
+fn ray(origin: Vec3, direction: Vec3) -> Ray {
+    Ray {
+        origin,
+        direction,
+        time: 0.0,
+        wavelength: None,
+    }
+}
+
+/// Unit sphere at the origin.
+fn unit_sphere() -> Sphere {
+    Sphere {
+        center: vec3(0.0, 0.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Box::new(Lambertian {
+            albedo: Color {
+                x: 0.5,
+                y: 0.5,
+                z: 0.5,
+            },
+        })),
+    }
+}
+
+/// With the `simd` feature the vector operations run as SSE kernels, which must round bit for
+/// bit like these formulas for renders to come out the same.
+#[test]
+fn vector_math_rounds_like_the_scalar_formulas() {
+    seed_random(6);
+    let random = || 200.0 * (random_float() - 0.5);
+    let bits = |v: Vec3| [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()];
+    for _ in 0..10000 {
+        let a = vec3(random(), random(), random());
+        let b = vec3(random(), random(), random());
+        let s = random();
+        assert_eq!(bits(a + b), bits(vec3(a.x + b.x, a.y + b.y, a.z + b.z)));
+        assert_eq!(bits(a - b), bits(vec3(a.x - b.x, a.y - b.y, a.z - b.z)));
+        assert_eq!(bits(a * b), bits(vec3(a.x * b.x, a.y * b.y, a.z * b.z)));
+        assert_eq!(bits(s * a), bits(vec3(a.x * s, a.y * s, a.z * s)));
+        assert_eq!(
+            dot_product(a, b).to_bits(),
+            (a.x * b.x + a.y * b.y + a.z * b.z).to_bits()
+        );
+        assert_eq!(
+            bits(cross_product(a, b)),
+            bits(vec3(
+                a.y * b.z - a.z * b.y,
+                a.z * b.x - a.x * b.z,
+                a.x * b.y - a.y * b.x
+            ))
+        );
+        assert_eq!(
+            a.len().to_bits(),
+            (a.x * a.x + a.y * a.y + a.z * a.z).sqrt().to_bits()
+        );
+    }
+}
+
+#[test]
+fn refraction_follows_snells_law() {
+    let normal = unit_vector(vec3(0.2, 1.0, -0.3));
+    let tangent = unit_vector(cross_product(normal, vec3(1.0, 0.0, 0.0)));
+    for eta in [1.0 / 1.5, 1.0, 1.5 / 1.33] {
+        for cos_i in [1.0 as Float, 0.9, 0.5, 0.2] {
+            let sin_i = (1.0 - cos_i * cos_i).sqrt();
+            if eta * sin_i > 1.0 {
+                // Totally reflected, which materials check for before refracting.
+                continue;
+            }
+            let incident = sin_i * tangent - cos_i * normal;
+            let refracted = refract(incident, normal, eta);
+            assert!((refracted.len() - 1.0).abs() < 1e-4);
+            // It leaves on the other side, in the plane of incidence.
+            assert!(dot_product(refracted, normal) < 0.0);
+            assert!(dot_product(refracted, cross_product(incident, normal)).abs() < 1e-4);
+            let sin_t = dot_product(refracted, tangent);
+            assert!(
+                (sin_t - eta * sin_i).abs() < 1e-4,
+                "sin θt {} for sin θi {} and η {}",
+                sin_t,
+                sin_i,
+                eta
+            );
+        }
+    }
+    let straight = refract(-normal, normal, 1.5);
+    assert!((straight + normal).len() < 1e-5);
+}
+
+#[test]
+fn ray_from_inside_hits_where_it_leaves() {
+    let sphere = unit_sphere();
+    let hit = sphere
+        .hit(
+            &ray(vec3(0.0, 0.0, 0.0), vec3(0.0, 2.0, 0.0)),
+            0.001,
+            Float::MAX,
+        )
+        .unwrap();
+    assert!((hit.t - 0.5).abs() < 1e-5);
+    assert!((hit.p - vec3(0.0, 1.0, 0.0)).len() < 1e-5);
+    assert!(!hit.front_face);
+    // The normal faces the ray.
+    assert!((hit.normal - vec3(0.0, -1.0, 0.0)).len() < 1e-5);
+}
+
+/// A negative radius makes a hollow sphere, the same surface with its normals turned inward.
+#[test]
+fn hollow_spheres_face_inward() {
+    let sphere = Sphere {
+        radius: -1.0,
+        ..unit_sphere()
+    };
+    let hit = sphere
+        .hit(
+            &ray(vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, -1.0)),
+            0.001,
+            Float::MAX,
+        )
+        .unwrap();
+    assert!((hit.t - 4.0).abs() < 1e-5);
+    assert!((hit.p - vec3(0.0, 0.0, 1.0)).len() < 1e-5);
+    assert!(!hit.front_face);
+    for _ in 0..100 {
+        let (sample, _) = sphere.sample_surface().unwrap();
+        assert!((sample.p.len() - 1.0).abs() < 1e-5);
+        assert!((sample.normal + sample.p).len() < 1e-4);
+    }
+}
+
+#[test]
+fn tangent_ray_touches_the_sphere() {
+    let sphere = unit_sphere();
+    let hit = sphere
+        .hit(
+            &ray(vec3(1.0, 0.0, 5.0), vec3(0.0, 0.0, -1.0)),
+            0.001,
+            Float::MAX,
+        )
+        .unwrap();
+    assert!((hit.t - 5.0).abs() < 1e-5);
+    assert!((hit.p - vec3(1.0, 0.0, 0.0)).len() < 1e-5);
+    // The ray runs along the surface, so it may be taken to meet either side of it.
+    assert!((hit.normal.x.abs() - 1.0).abs() < 1e-5);
+    let past = ray(vec3(1.001, 0.0, 5.0), vec3(0.0, 0.0, -1.0));
+    assert!(sphere.hit(&past, 0.001, Float::MAX).is_none());
+}
+
+#[test]
+fn hits_are_limited_to_the_interval() {
+    let sphere = unit_sphere();
+    // Enters the sphere at t = 4 and leaves at t = 6.
+    let through = ray(vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, -1.0));
+    let t = |t_min: Float, t_max: Float| sphere.hit(&through, t_min, t_max).map(|hit| hit.t);
+    assert_eq!(t(0.001, Float::MAX), Some(4.0));
+    // `t_max` is included, `t_min` is not.
+    assert_eq!(t(0.001, 4.0), Some(4.0));
+    assert_eq!(t(4.0, Float::MAX), Some(6.0));
+    assert_eq!(t(0.001, 3.999), None);
+    assert_eq!(t(6.0, Float::MAX), None);
+    assert_eq!(t(4.5, 5.5), None);
+    // Behind the origin of the ray.
+    let away = ray(vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, 1.0));
+    assert!(sphere.hit(&away, 0.001, Float::MAX).is_none());
+}
+
+/// Fully transparent texture counting the intersections tested against it.
+#[derive(Default)]
+struct Holes {
+    lookups: AtomicUsize,
+}
+
+impl Texture for Holes {
+    fn value(&self, _hit_record: &HitRecord) -> Color {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        Color {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+}
+
+/// Rays going through a masked sphere meet both of its sides, even on one much smaller than a
+/// fixed step past each transparent hit would be.
+#[test]
+fn alpha_masks_let_rays_through_each_surface_once() {
+    let radius = 2e-5;
+    let holes = Arc::new(Holes::default());
+    let masked = AlphaMasked {
+        object: Box::new(Sphere {
+            center: vec3(0.0, 0.0, -0.01),
+            radius,
+            ..unit_sphere()
+        }),
+        opacity: holes.clone(),
+        mode: AlphaMode::Threshold(0.5),
+    };
+    for i in 0..100 {
+        let offset = radius * i as Float / 200.0;
+        let r = ray(vec3(offset, 0.0, 0.0), vec3(0.0, 0.0, -1.0));
+        assert!(masked.hit(&r, 0.0, Float::MAX).is_none());
+    }
+    assert_eq!(holes.lookups.load(Ordering::Relaxed), 200);
+}
+
+/// Packets find the same hits as rays traced one at a time, whether their lanes are SSE
+/// registers or plain arrays, in packets filling any number of groups of four.
+#[test]
+fn packets_find_the_same_hits_as_single_rays() {
+    // The cutouts scene is left out, since its stochastic alpha mask hits at random.
+    for scene in ["random", "materials", "caustics"] {
+        let args: Vec<String> = ["rust-raytracer", "--seed", "1", "64", "--scene", scene]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let settings = RenderSettings::from_args(&args);
+        let scene = scenes::from_settings(&settings).unwrap();
+        let camera = build_camera(&settings, &scene, scene.view, 0.0).unwrap();
+        let (width, height) = (settings.image_width, settings.image_height());
+        let rays: Vec<Ray> = (0..height)
+            .flat_map(|j| (0..width).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                camera.get_ray(
+                    (i as Float + 0.5) / width as Float,
+                    (j as Float + 0.5) / height as Float,
+                )
+            })
+            .collect();
+        for size in [1, 5, 8, 32] {
+            for chunk in rays.chunks(size) {
+                let packet = RayPacket::new(chunk.to_vec());
+                let hits = scene.world.hit_packet(&packet, 0.0, Float::MAX);
+                for (ray, packet_hit) in chunk.iter().zip(hits) {
+                    let single_hit = scene.world.hit(ray, 0.0, Float::MAX);
+                    match (single_hit, packet_hit) {
+                        (Some(a), Some(b)) => {
+                            assert_eq!(a.object_id, b.object_id);
+                            assert_eq!(a.t.to_bits(), b.t.to_bits());
+                        }
+                        (None, None) => {}
+                        _ => panic!("only one of the ways finds a hit"),
+                    }
+                }
+            }
+        }
+    }
+}
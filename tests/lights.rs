@@ -0,0 +1,244 @@
+//! Tests of the light the path tracer gathers from point lights. A unit sphere at the origin,
+//! alone under a black sky, is seen from straight above with a point light above it, so all the
+//! light leaving it comes from the light.
+
+use std::sync::Arc;
+
+use rust_raytracer::{
+    float::{consts::PI, Float},
+    hittable::{Hittable, Quad, Sphere},
+    integrator::{
+        Background, Bidirectional, DepthLimits, Integrator, PathTracer, PhotonMapper,
+        PhotonSettings,
+    },
+    light::{Falloff, Light},
+    material::{Coated, Conductor, Lambertian, Material, Metal, Principled, RoughDielectric},
+    ray::Ray,
+    vec3::{Color, Point3, Vec3},
+    BoxedHittable, Camera, SharedMaterial, World,
+};
+
+const BLACK: Color = Color {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+fn point(x: Float, y: Float, z: Float) -> Point3 {
+    Point3 { x, y, z }
+}
+
+fn shared(material: impl Material + Send + Sync + 'static) -> SharedMaterial {
+    Arc::new(Box::new(material))
+}
+
+fn sphere(material: SharedMaterial) -> BoxedHittable {
+    Box::new(Sphere {
+        center: point(0.0, 0.0, 0.0),
+        radius: 1.0,
+        material,
+    })
+}
+
+/// Ray coming down onto the top of the sphere.
+fn from_above() -> Ray {
+    Ray {
+        origin: point(0.0, 0.0, 3.0),
+        direction: Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: -1.0,
+        },
+        time: 0.0,
+        wavelength: None,
+    }
+}
+
+/// Point light of intensity 25 at 5 units above the top of the sphere, which gives it an
+/// irradiance of 1.
+fn light() -> Light {
+    Light::Point {
+        position: point(0.0, 0.0, 6.0),
+        intensity: Color {
+            x: 25.0,
+            y: 25.0,
+            z: 25.0,
+        },
+        falloff: Falloff::InverseSquare,
+    }
+}
+
+fn radiance_from_above(integrator: &dyn Integrator, world: &World) -> Color {
+    let camera = Camera::builder().build().unwrap();
+    integrator.radiance(&from_above(), world, &camera, &mut vec![])
+}
+
+fn path_tracer() -> PathTracer {
+    PathTracer {
+        lights: vec![light()],
+        depths: DepthLimits::default(),
+        clamp: None,
+        background: Background::Uniform(BLACK),
+    }
+}
+
+/// Radiance leaving the top of a sphere made of `material`, lit by `light`.
+fn lit_from_above(material: SharedMaterial) -> Color {
+    let world = World::new(vec![sphere(material)]);
+    radiance_from_above(&path_tracer(), &world)
+}
+
+#[test]
+fn point_lights_reach_diffuse_surfaces() {
+    let radiance = lit_from_above(shared(Lambertian {
+        albedo: Color {
+            x: 0.5,
+            y: 0.5,
+            z: 0.5,
+        },
+    }));
+    assert!((radiance.x - 0.5 / PI).abs() < 1e-4, "{}", radiance.x);
+}
+
+#[test]
+fn point_lights_reach_every_integrator() {
+    let world = World::new(vec![sphere(shared(Lambertian {
+        albedo: Color {
+            x: 0.5,
+            y: 0.5,
+            z: 0.5,
+        },
+    }))]);
+    let background = Background::Uniform(BLACK);
+    let photon_mapper = |passes: usize| {
+        PhotonMapper::new(
+            &world,
+            vec![light()],
+            DepthLimits::default(),
+            background,
+            None,
+            PhotonSettings {
+                passes,
+                ..PhotonSettings::default()
+            },
+            0.0,
+        )
+    };
+    let integrators: [(&str, Box<dyn Integrator>); 3] = [
+        (
+            "bdpt",
+            Box::new(Bidirectional::new(
+                &world,
+                vec![light()],
+                DepthLimits::default(),
+                background,
+            )),
+        ),
+        ("photon", Box::new(photon_mapper(1))),
+        ("sppm", Box::new(photon_mapper(2))),
+    ];
+    for (name, integrator) in integrators {
+        let radiance = radiance_from_above(integrator.as_ref(), &world);
+        assert!(
+            (radiance.x - 0.5 / PI).abs() < 1e-4,
+            "{} gives {}",
+            name,
+            radiance.x
+        );
+    }
+}
+
+/// Shadow rays are tested all the way to the light, so a blocker just in front of it, 2e-3
+/// away on a path of length 5, still casts a shadow.
+#[test]
+fn blockers_right_next_to_point_lights_cast_shadows() {
+    let blocker = Box::new(Quad {
+        corner: point(-0.5, -0.5, 5.998),
+        u: point(1.0, 0.0, 0.0),
+        v: point(0.0, 1.0, 0.0),
+        material: shared(Lambertian { albedo: BLACK }),
+    });
+    let lambertian = shared(Lambertian {
+        albedo: Color {
+            x: 0.5,
+            y: 0.5,
+            z: 0.5,
+        },
+    });
+    let world = World::new(vec![sphere(lambertian), blocker]);
+    let radiance = radiance_from_above(&path_tracer(), &world);
+    assert_eq!(radiance.x, 0.0);
+}
+
+/// Seen and lit along the normal, GGX reflects `F D / 4` of the irradiance, with the density of
+/// normals `D = 1 / (π α²)` for the facets facing straight up.
+#[test]
+fn point_lights_reach_rough_metal() {
+    let conductor = Conductor::silver(0.4);
+    let alpha: Float = 0.4 * 0.4;
+    let hit_record = sphere(shared(Lambertian { albedo: BLACK }))
+        .hit(&from_above(), 0.0, Float::MAX)
+        .unwrap();
+    let expected = conductor.albedo(&hit_record) / (4.0 * PI * alpha * alpha);
+    let radiance = lit_from_above(shared(conductor));
+    assert!(
+        (radiance - expected).len() < 1e-3 * expected.len(),
+        "{} {} {} instead of {} {} {}",
+        radiance.x,
+        radiance.y,
+        radiance.z,
+        expected.x,
+        expected.y,
+        expected.z
+    );
+}
+
+#[test]
+fn point_lights_reach_every_glossy_material() {
+    let white = Color {
+        x: 1.0,
+        y: 1.0,
+        z: 1.0,
+    };
+    let materials = [
+        (
+            "metal",
+            shared(Metal {
+                albedo: white,
+                fuzzines: 0.3,
+            }),
+        ),
+        (
+            "rough dielectric",
+            shared(RoughDielectric::new(1.5, 0.3, 0.3)),
+        ),
+        ("principled", shared(Principled::default())),
+        (
+            "principled metal",
+            shared(Principled {
+                metallic: 1.0,
+                ..Principled::default()
+            }),
+        ),
+        (
+            "coated",
+            shared(Coated {
+                base: shared(Conductor::gold(0.3)),
+                ir: 1.5,
+                roughness: 0.1,
+                tint: white,
+            }),
+        ),
+    ];
+    for (name, material) in materials {
+        let radiance = lit_from_above(material);
+        assert!(
+            radiance.x > 0.01 && radiance.y > 0.01 && radiance.z > 0.01,
+            "{} reflects {} {} {}",
+            name,
+            radiance.x,
+            radiance.y,
+            radiance.z
+        );
+    }
+}
@@ -0,0 +1,467 @@
+//! White furnace and reciprocity tests of the materials. Light arriving from `wo` is scattered
+//! many times at the top of a unit sphere, whose normal there is `+z`, and the weights returned
+//! are averaged.
+
+use std::sync::Arc;
+
+use rust_raytracer::{
+    float::{consts::PI, Float},
+    hittable::{HitRecord, Hittable, Sphere},
+    material::{
+        Coated, Conductor, Dielectric, DiffuseLight, Lambertian, Material, Metal, Mix, MixWeight,
+        Principled, RoughDielectric, SharedMaterial, TwoSided,
+    },
+    random_in_unit_sphere,
+    ray::Ray,
+    seed_random,
+    vec3::{dot_product, unit_vector, Color, Vec3},
+};
+
+const SAMPLES: usize = 100_000;
+
+const WHITE: Color = Color {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+};
+
+const NORMAL: Vec3 = Vec3 {
+    x: 0.0,
+    y: 0.0,
+    z: 1.0,
+};
+
+fn shared(material: impl Material + Send + Sync + 'static) -> SharedMaterial {
+    Arc::new(Box::new(material))
+}
+
+/// Hit at the top of a unit sphere at the origin, seen from above.
+fn surface() -> HitRecord {
+    let sphere = Sphere {
+        center: Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        radius: 1.0,
+        material: shared(Lambertian { albedo: WHITE }),
+    };
+    let ray = Ray {
+        origin: 2.0 * NORMAL,
+        direction: -NORMAL,
+        time: 0.0,
+        wavelength: None,
+    };
+    sphere.hit(&ray, 0.0, Float::MAX).unwrap()
+}
+
+/// Ray arriving at `hit_record` from the unit direction `wo`.
+fn arriving(hit_record: &HitRecord, wo: Vec3) -> Ray {
+    Ray {
+        origin: hit_record.p + wo,
+        direction: -wo,
+        time: 0.0,
+        wavelength: None,
+    }
+}
+
+/// Unit direction at `cos_theta` to the normal.
+fn at_angle(cos_theta: Float) -> Vec3 {
+    Vec3 {
+        x: (1.0 - cos_theta * cos_theta).sqrt(),
+        y: 0.0,
+        z: cos_theta,
+    }
+}
+
+/// Direction above the surface with a density proportional to its cosine to the normal.
+fn cosine_direction() -> Vec3 {
+    loop {
+        let direction = NORMAL + unit_vector(random_in_unit_sphere());
+        if !direction.near_zero() {
+            return unit_vector(direction);
+        }
+    }
+}
+
+/// Mean weight of the light scattered from `wo`, counting absorbed samples as zero: the
+/// fraction of the light arriving from `wo` that leaves the surface.
+fn reflected(material: &dyn Material, wo: Vec3) -> Color {
+    let hit_record = surface();
+    let ray = arriving(&hit_record, wo);
+    let mut total = Color {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    for _ in 0..SAMPLES {
+        if let Some((attenuation, _, _)) = material.scatter(&ray, &hit_record) {
+            total += &attenuation;
+        }
+    }
+    total / SAMPLES as Float
+}
+
+/// Every material, with white colors where it has any.
+fn materials() -> Vec<(&'static str, SharedMaterial)> {
+    let lambertian = shared(Lambertian { albedo: WHITE });
+    vec![
+        ("lambertian", Arc::clone(&lambertian)),
+        ("light", shared(DiffuseLight { emit: WHITE })),
+        (
+            "metal",
+            shared(Metal {
+                albedo: WHITE,
+                fuzzines: 0.5,
+            }),
+        ),
+        (
+            "dielectric",
+            shared(Dielectric {
+                ir: 1.5,
+                dispersion: None,
+                absorption: None,
+            }),
+        ),
+        ("conductor", shared(Conductor::silver(0.4))),
+        (
+            "rough dielectric",
+            shared(RoughDielectric::new(1.5, 0.4, 0.4)),
+        ),
+        (
+            "principled",
+            shared(Principled {
+                base_color: WHITE,
+                ..Principled::default()
+            }),
+        ),
+        (
+            "principled metal",
+            shared(Principled {
+                base_color: WHITE,
+                metallic: 1.0,
+                anisotropic: 0.8,
+                ..Principled::default()
+            }),
+        ),
+        (
+            "principled sheen",
+            shared(Principled {
+                base_color: WHITE,
+                // Leaves the diffuse lobe alone, so any light the sheen adds shows.
+                specular: 0.0,
+                sheen: 1.0,
+                sheen_tint: 0.0,
+                ..Principled::default()
+            }),
+        ),
+        (
+            "principled cloth",
+            shared(Principled {
+                base_color: Color {
+                    x: 1.0,
+                    y: 0.2,
+                    z: 0.1,
+                },
+                roughness: 0.9,
+                sheen: 1.0,
+                sheen_tint: 1.0,
+                ..Principled::default()
+            }),
+        ),
+        (
+            "principled rough coat",
+            shared(Principled {
+                base_color: WHITE,
+                roughness: 0.8,
+                clearcoat: 1.0,
+                clearcoat_roughness: 0.6,
+                ..Principled::default()
+            }),
+        ),
+        (
+            "principled glass",
+            shared(Principled {
+                base_color: WHITE,
+                transmission: 1.0,
+                clearcoat: 1.0,
+                ..Principled::default()
+            }),
+        ),
+        (
+            "coated",
+            shared(Coated {
+                base: Arc::clone(&lambertian),
+                ir: 1.5,
+                roughness: 0.2,
+                tint: WHITE,
+            }),
+        ),
+        (
+            "mix",
+            shared(Mix {
+                a: Arc::clone(&lambertian),
+                b: shared(Conductor::silver(0.1)),
+                weight: MixWeight::Scalar(0.5),
+            }),
+        ),
+        (
+            "two sided",
+            shared(TwoSided {
+                material: Arc::clone(&lambertian),
+            }),
+        ),
+    ]
+}
+
+#[test]
+fn no_material_reflects_more_light_than_it_receives() {
+    seed_random(1);
+    for (name, material) in materials() {
+        for cos_theta in [1.0, 0.7, 0.3, 0.05] {
+            let r = reflected(material.as_ref().as_ref(), at_angle(cos_theta));
+            // The mean of weights of at most one is within a few thousandths of its expectation.
+            assert!(
+                r.x.max(r.y).max(r.z) < 1.005,
+                "{} reflects {} {} {} of the light arriving at cos θ = {}",
+                name,
+                r.x,
+                r.y,
+                r.z,
+                cos_theta
+            );
+        }
+    }
+}
+
+/// With directions drawn by `scatter`, the mean of `eval / pdf` is the light it reflects, which
+/// only holds if `pdf` is the density of those directions and `eval` matches their weights.
+#[test]
+fn eval_and_pdf_agree_with_scatter() {
+    seed_random(5);
+    let hit_record = surface();
+    for (name, material) in materials() {
+        for cos_theta in [0.9, 0.4] {
+            let wo = at_angle(cos_theta);
+            if material.eval(&hit_record, wo, wo).is_none() {
+                continue;
+            }
+            let ray = arriving(&hit_record, wo);
+            let mut sampled = Color {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            };
+            let mut evaluated = sampled;
+            for _ in 0..SAMPLES {
+                if let Some((attenuation, scattered, _)) = material.scatter(&ray, &hit_record) {
+                    let wi = unit_vector(scattered.direction);
+                    let pdf = material.pdf(&hit_record, wo, wi);
+                    assert!(pdf > 0.0, "{} scatters where its pdf is zero", name);
+                    sampled += &attenuation;
+                    evaluated += &(material.eval(&hit_record, wo, wi).unwrap() / pdf);
+                }
+            }
+            let difference = (sampled - evaluated) / SAMPLES as Float;
+            assert!(
+                difference.len() < 0.01,
+                "{} scatters {} {} {} more than it evaluates to at cos θ = {}",
+                name,
+                difference.x,
+                difference.y,
+                difference.z,
+                cos_theta
+            );
+        }
+    }
+}
+
+/// Combinators pass on the ideal rays and the emission of the materials they wrap, so that
+/// renderers following ideal rays see through mixed or coated glass and light up emissive ones.
+#[test]
+fn combinators_forward_ideal_rays_and_emission() {
+    let hit_record = surface();
+    let ray = arriving(&hit_record, at_angle(0.8));
+    let glass = shared(Dielectric {
+        ir: 1.5,
+        dispersion: None,
+        absorption: None,
+    });
+    let lambertian = shared(Lambertian { albedo: WHITE });
+    let light = shared(DiffuseLight { emit: WHITE });
+    let mix = |a: &SharedMaterial, b: &SharedMaterial| Mix {
+        a: Arc::clone(a),
+        b: Arc::clone(b),
+        weight: MixWeight::Scalar(0.25),
+    };
+    let coat = |base: &SharedMaterial| Coated {
+        base: Arc::clone(base),
+        ir: 1.5,
+        roughness: 0.2,
+        tint: WHITE,
+    };
+    let total = |rays: Vec<(Color, Ray)>| rays.iter().map(|(weight, _)| weight.x).sum::<Float>();
+
+    let mirror = shared(Conductor::silver(0.0));
+    let glass_or_mirror = mix(&glass, &mirror).specular(&ray, &hit_record);
+    assert_eq!(glass_or_mirror.len(), 3);
+    let expected = 0.75 + 0.25 * total(mirror.specular(&ray, &hit_record));
+    assert!((total(glass_or_mirror) - expected).abs() < 1e-5);
+    let coated_glass = coat(&glass).specular(&ray, &hit_record);
+    assert_eq!(coated_glass.len(), 3);
+    assert!((total(coated_glass) - 1.0).abs() < 1e-5);
+    // Shaded as diffuse, like the material they are partly made of.
+    assert!(mix(&lambertian, &glass)
+        .specular(&ray, &hit_record)
+        .is_empty());
+    assert!(coat(&lambertian).specular(&ray, &hit_record).is_empty());
+
+    assert_eq!(mix(&light, &lambertian).emitted(&hit_record).x, 0.75);
+    assert_eq!(coat(&light).emitted(&hit_record).x, 1.0);
+    let mut back = hit_record.clone();
+    back.front_face = false;
+    assert_eq!(light.emitted(&back).x, 0.0);
+    let two_sided = TwoSided {
+        material: Arc::clone(&light),
+    };
+    assert_eq!(two_sided.emitted(&back).x, 1.0);
+}
+
+#[test]
+fn lossless_materials_reflect_all_light() {
+    seed_random(2);
+    let lossless = [
+        ("lambertian", shared(Lambertian { albedo: WHITE })),
+        (
+            "mirror",
+            shared(Metal {
+                albedo: WHITE,
+                fuzzines: 0.0,
+            }),
+        ),
+        (
+            "dielectric",
+            shared(Dielectric {
+                ir: 1.5,
+                dispersion: None,
+                absorption: None,
+            }),
+        ),
+    ];
+    for (name, material) in lossless {
+        for cos_theta in [1.0, 0.5, 0.05] {
+            let r = reflected(material.as_ref().as_ref(), at_angle(cos_theta));
+            assert!(
+                (r.x - 1.0).abs() < 1e-3 && (r.y - 1.0).abs() < 1e-3 && (r.z - 1.0).abs() < 1e-3,
+                "{} reflects {} {} {} of the light arriving at cos θ = {}",
+                name,
+                r.x,
+                r.y,
+                r.z,
+                cos_theta
+            );
+        }
+    }
+}
+
+#[test]
+fn lambertian_eval_is_reciprocal_and_integrates_to_its_albedo() {
+    seed_random(3);
+    let hit_record = surface();
+    let albedo = Color {
+        x: 0.9,
+        y: 0.5,
+        z: 0.1,
+    };
+    let material = Lambertian { albedo };
+    let pdf = |w: Vec3| dot_product(w, NORMAL) / PI;
+    let mut total = Color {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let wo = at_angle(0.6);
+    for _ in 0..SAMPLES {
+        let wi = cosine_direction();
+        let forward = material.eval(&hit_record, wo, wi).unwrap();
+        let backward = material.eval(&hit_record, wi, wo).unwrap();
+        // `eval` includes the cosine of the incident direction, which swaps with them.
+        let difference = forward / dot_product(wi, NORMAL) - backward / dot_product(wo, NORMAL);
+        assert!(difference.len() < 1e-4);
+        assert!((material.pdf(&hit_record, wo, wi) - pdf(wi)).abs() < 1e-4);
+        total += &(forward / pdf(wi));
+    }
+    let integral = total / SAMPLES as Float;
+    assert!((integral - albedo).len() < 1e-3);
+}
+
+/// Light reflected between two bands of directions, `cos θ` in `from` and in `to`, by
+/// `material` lit from every direction above it with a density proportional to `cos θ`, as a
+/// mean and its standard error.
+fn transport(material: &dyn Material, from: (Float, Float), to: (Float, Float)) -> (Float, Float) {
+    let hit_record = surface();
+    let within = |w: Vec3, band: (Float, Float)| {
+        let cos_theta = dot_product(unit_vector(w), NORMAL);
+        band.0 <= cos_theta && cos_theta < band.1
+    };
+    let mut sum = 0.0;
+    let mut sum_squared = 0.0;
+    for _ in 0..SAMPLES {
+        let wo = cosine_direction();
+        if !within(wo, from) {
+            continue;
+        }
+        if let Some((attenuation, scattered, _)) =
+            material.scatter(&arriving(&hit_record, wo), &hit_record)
+        {
+            if within(scattered.direction, to) {
+                let weight = (attenuation.x + attenuation.y + attenuation.z) / 3.0;
+                sum += weight;
+                sum_squared += weight * weight;
+            }
+        }
+    }
+    let n = SAMPLES as Float;
+    let mean = sum / n;
+    (mean, ((sum_squared / n - mean * mean) / n).sqrt())
+}
+
+/// Physically based reflection transports as much light from one band of directions to another
+/// as back. The fuzzy `Metal` and the diffuse lobes of the layered materials, whose weights only
+/// depend on the direction light arrives from, are approximations that do not.
+#[test]
+fn physically_based_reflection_is_reciprocal() {
+    seed_random(4);
+    let reciprocal = [
+        ("lambertian", shared(Lambertian { albedo: WHITE })),
+        ("conductor", shared(Conductor::gold(0.5))),
+        (
+            "rough dielectric",
+            shared(RoughDielectric::new(1.5, 0.5, 0.5)),
+        ),
+        (
+            "principled metal",
+            shared(Principled {
+                base_color: WHITE,
+                metallic: 1.0,
+                roughness: 0.6,
+                ..Principled::default()
+            }),
+        ),
+    ];
+    let steep = (0.8, 1.0);
+    let grazing = (0.2, 0.5);
+    for (name, material) in reciprocal {
+        let (forward, forward_error) = transport(material.as_ref().as_ref(), steep, grazing);
+        let (backward, backward_error) = transport(material.as_ref().as_ref(), grazing, steep);
+        let error = forward_error.hypot(backward_error);
+        assert!(
+            forward > 0.0 && (forward - backward).abs() < 4.0 * error,
+            "{} reflects {} from steep to grazing directions but {} back, error {}",
+            name,
+            forward,
+            backward,
+            error
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Tests of reading PPM files, which `diff` reads from wherever it is pointed.
+
+use rust_raytracer::ppm::read_ppm;
+
+/// Reads `contents` back from a temporary file.
+fn read(name: &str, contents: &[u8]) -> Result<(i32, i32), String> {
+    let path = std::env::temp_dir().join(format!("rust-raytracer-{}.ppm", name));
+    std::fs::write(&path, contents).unwrap();
+    let image = read_ppm(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    image
+        .map(|image| (image.width, image.height))
+        .map_err(|e| e.to_string())
+}
+
+#[test]
+fn valid_headers_are_read() {
+    assert_eq!(
+        read("ascii", b"P3\n# comment\n2 1\n255\n0 0 0 255 255 255\n"),
+        Ok((2, 1))
+    );
+    assert_eq!(
+        read("binary", b"P6 1 1 65535\n\x00\x01\x00\x02\x00\x03"),
+        Ok((1, 1))
+    );
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    let cases: [(&str, &[u8], &str); 6] = [
+        (
+            "negative",
+            b"P3\n-2 -1\n255\n0 0 0 0 0 0\n",
+            "dimensions must be positive",
+        ),
+        ("empty", b"P3\n0 1\n255\n", "dimensions must be positive"),
+        ("zero-max", b"P3\n1 1\n0\n0 0 0\n", "maximum value"),
+        ("wide-max", b"P3\n1 1\n65536\n0 0 0\n", "maximum value"),
+        ("overflow", b"P3\n65536 65536\n255\n0 0 0\n", "too large"),
+        ("truncated", b"P3\n2 2\n255\n0 0 0\n", "truncated"),
+    ];
+    for (name, contents, message) in cases {
+        match read(name, contents) {
+            Ok(size) => panic!("{} was read as {:?}", name, size),
+            Err(e) => assert!(e.contains(message), "{}: {}", name, e),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Renders small versions of the demo scenes at a fixed seed and compares them to the reference
+//! images in `tests/references`. Run with `UPDATE_REFERENCES=1` to render the references again
+//! after an intended change of the output.
+
+use rust_raytracer::{
+    diff::compare,
+    float::Float,
+    ppm::{generate_ppm, read_ppm_srgb, save_ppm},
+    render, scenes, Image, RenderSettings,
+};
+
+const SEED: &str = "1";
+const WIDTH: &str = "96";
+/// Renders are compared at this fraction of their size, where their noise averages out.
+const SHRINK: i32 = 4;
+
+/// Render checked against a reference. Other platforms, and the `f64` feature, draw other random
+/// numbers and so other noise; `max_flip` leaves room for it, but not for the error of a render
+/// 10% darker than the reference.
+struct Case {
+    name: &'static str,
+    args: &'static [&'static str],
+    max_flip: Float,
+}
+
+fn check(case: Case) {
+    let args: Vec<String> = ["rust-raytracer", "--seed", SEED, WIDTH]
+        .iter()
+        .chain(case.args)
+        .map(|arg| arg.to_string())
+        .collect();
+    let settings = RenderSettings::from_args(&args);
+    let scene = scenes::from_settings(&settings).unwrap();
+    let frame = render(&settings, &scene).unwrap();
+    let path = format!(
+        "{}/tests/references/{}.ppm",
+        env!("CARGO_MANIFEST_DIR"),
+        case.name
+    );
+    if std::env::var_os("UPDATE_REFERENCES").is_some() {
+        save_ppm(generate_ppm(&frame.image).as_str(), &path).unwrap();
+        return;
+    }
+    let reference = read_ppm_srgb(&path).unwrap_or_else(|e| {
+        panic!(
+            "cannot read {}: {}, render it with UPDATE_REFERENCES=1",
+            path, e
+        )
+    });
+    // Clipped and quantized like the reference, so the two are compared as they are displayed.
+    let rendered = Image::from_srgb8(
+        frame.image.width,
+        frame.image.height,
+        &frame.image.to_srgb8(),
+    );
+    let shrink = |image: &Image| image.resize(image.width / SHRINK, image.height / SHRINK);
+    let comparison = compare(&shrink(&reference), &shrink(&rendered)).unwrap();
+    println!(
+        "{}: RMSE {:.5} PSNR {:.2} SSIM {:.5} FLIP {:.5}",
+        case.name, comparison.rmse, comparison.psnr, comparison.ssim, comparison.flip
+    );
+    assert!(
+        comparison.flip <= case.max_flip,
+        "{} differs from its reference: FLIP {}, at most {}",
+        case.name,
+        comparison.flip,
+        case.max_flip
+    );
+}
+
+#[test]
+fn caustics_bidirectional() {
+    check(Case {
+        name: "caustics_bdpt",
+        args: &[
+            "--scene",
+            "caustics",
+            "--integrator",
+            "bdpt",
+            "--samples",
+            "16",
+        ],
+        max_flip: 0.032,
+    });
+}
+
+#[test]
+fn materials_path() {
+    check(Case {
+        name: "materials_path",
+        args: &["--scene", "materials", "--samples", "16"],
+        max_flip: 0.025,
+    });
+}
+
+#[test]
+fn materials_whitted() {
+    check(Case {
+        name: "materials_whitted",
+        args: &["--scene", "materials", "--integrator", "whitted"],
+        max_flip: 0.02,
+    });
+}
+
+#[test]
+fn cutouts_normals() {
+    check(Case {
+        name: "cutouts_normal",
+        args: &["--scene", "cutouts", "--integrator", "normal"],
+        max_flip: 0.03,
+    });
+}
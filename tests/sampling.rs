@@ -0,0 +1,139 @@
+//! Chi-squared tests of the distributions of the random sampling helpers. Each reduces its
+//! samples to quantities that are uniform in `[0, 1)` when the samples follow the intended
+//! distribution.
+
+use std::sync::Arc;
+
+use rust_raytracer::{
+    float::{consts::PI, Float},
+    hittable::{Hittable, Sphere},
+    material::{Lambertian, Material},
+    random_in_unit_disk, random_in_unit_sphere,
+    ray::Ray,
+    seed_random,
+    vec3::{dot_product, orthonormal_basis, unit_vector, Color, Vec3},
+};
+
+const SAMPLES: usize = 100_000;
+const BINS: usize = 20;
+/// Chi-squared statistic with `BINS - 1` degrees of freedom that is exceeded with probability
+/// 0.001 by uniform samples.
+const CRITICAL: Float = 43.82;
+
+/// Pearson's chi-squared statistic of `values` against the uniform distribution over `[0, 1)`.
+fn chi_squared(values: &[Float]) -> Float {
+    let mut counts = [0usize; BINS];
+    for value in values {
+        counts[((value * BINS as Float) as usize).min(BINS - 1)] += 1;
+    }
+    let expected = values.len() as Float / BINS as Float;
+    counts
+        .iter()
+        .map(|count| {
+            let d = *count as Float - expected;
+            d * d / expected
+        })
+        .sum()
+}
+
+fn assert_uniform(name: &str, values: &[Float]) {
+    assert!(
+        values.iter().all(|v| (0.0..=1.0).contains(v)),
+        "{} out of [0, 1]",
+        name
+    );
+    let statistic = chi_squared(values);
+    assert!(
+        statistic < CRITICAL,
+        "{} is not uniform: chi-squared {} over {}",
+        name,
+        statistic,
+        CRITICAL
+    );
+}
+
+/// Position of the angle `phi` in `(-π, π]` around a full turn.
+fn turn(phi: Float) -> Float {
+    (phi + PI) / (2.0 * PI)
+}
+
+#[test]
+fn unit_sphere_is_filled_uniformly() {
+    seed_random(1);
+    let samples: Vec<Vec3> = (0..SAMPLES).map(|_| random_in_unit_sphere()).collect();
+    assert!(samples.iter().all(|v| v.len_squared() < 1.0));
+    // The volume within radius r grows as r³, the area below height z on a sphere linearly.
+    let cubed_radius: Vec<Float> = samples.iter().map(|v| v.len().powi(3)).collect();
+    let height: Vec<Float> = samples
+        .iter()
+        .map(|v| 0.5 * (unit_vector(*v).z + 1.0))
+        .collect();
+    let azimuth: Vec<Float> = samples.iter().map(|v| turn(v.y.atan2(v.x))).collect();
+    assert_uniform("r³", &cubed_radius);
+    assert_uniform("z / r", &height);
+    assert_uniform("azimuth", &azimuth);
+}
+
+#[test]
+fn unit_disk_is_filled_uniformly() {
+    seed_random(2);
+    let samples: Vec<Vec3> = (0..SAMPLES).map(|_| random_in_unit_disk()).collect();
+    assert!(samples.iter().all(|p| p.z == 0.0 && p.len_squared() < 1.0));
+    let squared_radius: Vec<Float> = samples.iter().map(|p| p.len_squared()).collect();
+    let azimuth: Vec<Float> = samples.iter().map(|p| turn(p.y.atan2(p.x))).collect();
+    assert_uniform("r²", &squared_radius);
+    assert_uniform("azimuth", &azimuth);
+}
+
+/// Lambertian reflection samples directions with a density proportional to their cosine to the
+/// normal, the density it reports for them.
+#[test]
+fn lambertian_scattering_is_cosine_weighted() {
+    seed_random(3);
+    let normal = unit_vector(Vec3 {
+        x: 1.0,
+        y: 2.0,
+        z: -3.0,
+    });
+    let albedo = Color {
+        x: 0.5,
+        y: 0.5,
+        z: 0.5,
+    };
+    let material = Lambertian { albedo };
+    let sphere = Sphere {
+        center: Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        radius: 1.0,
+        material: Arc::new(Box::new(Lambertian { albedo })),
+    };
+    let incoming = Ray {
+        origin: 2.0 * normal,
+        direction: -normal,
+        time: 0.0,
+        wavelength: None,
+    };
+    let hit_record = sphere.hit(&incoming, 0.0, Float::MAX).unwrap();
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    let samples: Vec<Vec3> = (0..SAMPLES)
+        .map(|_| {
+            let (_, scattered, _) = material.scatter(&incoming, &hit_record).unwrap();
+            unit_vector(scattered.direction)
+        })
+        .collect();
+    assert!(samples.iter().all(|w| dot_product(*w, normal) >= 0.0));
+    // A density proportional to cos θ makes cos² θ uniform.
+    let squared_cosine: Vec<Float> = samples
+        .iter()
+        .map(|w| dot_product(*w, normal).powi(2))
+        .collect();
+    let azimuth: Vec<Float> = samples
+        .iter()
+        .map(|w| turn(dot_product(*w, bitangent).atan2(dot_product(*w, tangent))))
+        .collect();
+    assert_uniform("cos² θ", &squared_cosine);
+    assert_uniform("azimuth", &azimuth);
+}